@@ -0,0 +1,280 @@
+//! Language server of nexus, speaking the language server protocol over stdio.
+//!
+//! Supports diagnostics, hover, go to definition, completion and semantic tokens.
+//! Documents are always synced in full. See `nexus_lib::lsp` for the analysis
+//! and its limits. Columns are counted in chars instead of UTF-16 code units,
+//! which only makes a difference for chars outside of the basic multilingual plane
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use nexus_lib::lsp::{self, CompletionKind, SemanticKind};
+use serde_json::{json, Value};
+
+/// Error code of the protocol for unknown requests
+const METHOD_NOT_FOUND: i64 = -32601;
+
+struct Server {
+    /// Content of every open document by its uri
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+fn main() {
+    let mut server = Server {
+        documents: HashMap::new(),
+        shutdown: false,
+    };
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    while let Some(msg) = read_message(&mut stdin) {
+        let method = msg["method"].as_str().unwrap_or_default();
+        if method == "exit" {
+            std::process::exit(if server.shutdown { 0 } else { 1 });
+        }
+        match msg.get("id") {
+            Some(id) => {
+                let response = match server.request(method, &msg["params"]) {
+                    Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    None => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": METHOD_NOT_FOUND, "message": format!("Unknown method {}", method) },
+                    }),
+                };
+                write_message(&response);
+            }
+            None => server.notification(method, &msg["params"]),
+        }
+    }
+}
+
+impl Server {
+    /// Result of the request, or `None` if the method is unknown.
+    /// Requests for documents that are not open result in `null`
+    fn request(&mut self, method: &str, params: &Value) -> Option<Value> {
+        Some(match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": {},
+                    "semanticTokensProvider": {
+                        "legend": {
+                            "tokenTypes": SemanticKind::ALL.map(|kind| kind.lsp_name()),
+                            "tokenModifiers": [],
+                        },
+                        "full": true,
+                    },
+                },
+                "serverInfo": { "name": "nexus-lsp" },
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/hover" => {
+                let Some((source, line, col)) = self.position(params) else {
+                    return Some(Value::Null);
+                };
+                match lsp::hover(source, line, col) {
+                    Some(text) => json!({
+                        "contents": { "kind": "markdown", "value": hover_markdown(&text) },
+                    }),
+                    None => Value::Null,
+                }
+            }
+            "textDocument/definition" => {
+                let Some((source, line, col)) = self.position(params) else {
+                    return Some(Value::Null);
+                };
+                match lsp::definition(source, line, col) {
+                    Some(symbol) => {
+                        let start = source
+                            .lines()
+                            .nth(symbol.line - 1)
+                            .and_then(|text| text.find(&symbol.name).map(|pos| (text, pos)))
+                            .map_or(0, |(text, pos)| text[..pos].chars().count());
+                        json!({
+                            "uri": params["textDocument"]["uri"],
+                            "range": range(symbol.line, start, start + symbol.name.chars().count()),
+                        })
+                    }
+                    None => Value::Null,
+                }
+            }
+            "textDocument/completion" => {
+                let Some((source, line, _)) = self.position(params) else {
+                    return Some(Value::Null);
+                };
+                let items: Vec<Value> = lsp::completions(source, line)
+                    .into_iter()
+                    .map(|completion| {
+                        json!({
+                            "label": completion.label,
+                            "kind": match completion.kind {
+                                CompletionKind::Variable => 6,
+                                CompletionKind::Function | CompletionKind::Builtin => 3,
+                                CompletionKind::Keyword => 14,
+                            },
+                            "detail": completion.detail,
+                        })
+                    })
+                    .collect();
+                Value::Array(items)
+            }
+            "textDocument/semanticTokens/full" => {
+                let Some(source) = self.document(params) else {
+                    return Some(Value::Null);
+                };
+                json!({ "data": semantic_tokens_data(source) })
+            }
+            _ => return None,
+        })
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.into());
+                self.publish_diagnostics(&uri);
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|changes| changes.last()?["text"].as_str()) {
+                    self.documents.insert(uri.clone(), text.into());
+                    self.publish_diagnostics(&uri);
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.publish_diagnostics(&uri);
+            }
+            _ => (),
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: &str) {
+        let source = self.documents.get(uri).map_or("", String::as_str);
+        let diagnostics: Vec<Value> = lsp::diagnostics(source)
+            .into_iter()
+            .map(|diagnostic| {
+                // Only the line is known, so the whole line is marked
+                let len = source
+                    .lines()
+                    .nth(diagnostic.line - 1)
+                    .map_or(0, |text| text.chars().count());
+                json!({
+                    "range": range(diagnostic.line, 0, len),
+                    "severity": 1,
+                    "code": diagnostic.code.code(),
+                    "source": "nexus",
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+        write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }));
+    }
+
+    fn document(&self, params: &Value) -> Option<&str> {
+        self.documents
+            .get(params["textDocument"]["uri"].as_str()?)
+            .map(String::as_str)
+    }
+
+    /// Document, line (starting at 1) and column of a position of the protocol
+    fn position(&self, params: &Value) -> Option<(&str, usize, usize)> {
+        let source = self.document(params)?;
+        let line = params["position"]["line"].as_u64()? as usize + 1;
+        let col = params["position"]["character"].as_u64()? as usize;
+        Some((source, line, col))
+    }
+}
+
+/// Range of the protocol within a line starting at 1
+fn range(line: usize, start: usize, end: usize) -> Value {
+    json!({
+        "start": { "line": line - 1, "character": start },
+        "end": { "line": line - 1, "character": end },
+    })
+}
+
+/// Semantic tokens in the relative encoding of the protocol, five numbers per token.
+/// Tokens spanning multiple lines are cut off at the end of their first line
+fn semantic_tokens_data(source: &str) -> Vec<usize> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut data = Vec::new();
+    let (mut prev_line, mut prev_col) = (1, 0);
+    for tok in lsp::semantic_tokens(source) {
+        let len = chars[tok.range]
+            .iter()
+            .take_while(|ch| **ch != '\n')
+            .count();
+        let delta_col = match tok.line == prev_line {
+            true => tok.col - prev_col,
+            false => tok.col,
+        };
+        let kind = SemanticKind::ALL
+            .iter()
+            .position(|kind| *kind == tok.kind)
+            .unwrap_or_default();
+        data.extend([tok.line - prev_line, delta_col, len, kind, 0]);
+        (prev_line, prev_col) = (tok.line, tok.col);
+    }
+    data
+}
+
+/// Shows the declaration as code and the doc comments below it
+fn hover_markdown(text: &str) -> String {
+    match text.split_once("\n\n") {
+        Some((decl, docs)) => format!("```nexus\n{}\n```\n{}", decl, docs),
+        None => format!("```nexus\n{}\n```", text),
+    }
+}
+
+/// Reads a message with its `Content-Length` header.
+/// Returns `None` once stdin is closed
+fn read_message(stdin: &mut impl BufRead) -> Option<Value> {
+    let mut len = None;
+    loop {
+        let mut header = String::new();
+        if stdin.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            len = value.trim().parse::<usize>().ok();
+        }
+    }
+    let mut content = vec![0; len?];
+    stdin.read_exact(&mut content).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+fn write_message(msg: &Value) {
+    let content = msg.to_string();
+    let mut stdout = io::stdout().lock();
+    // The client is gone if writing fails, reading will end the loop then
+    let _ = write!(
+        stdout,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    );
+    let _ = stdout.flush();
+}
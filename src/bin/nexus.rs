@@ -0,0 +1,376 @@
+//! Command line interface of nexus
+//!
+//! ```text
+//! nexus run <files>    Runs the files as one program, in the given order
+//!   --profile          Prints the time spent in every function and line
+//!   --coverage         Prints which lines and branches ran
+//!                      and writes them to `lcov.info`, only for one file
+//! nexus check <files>  Parses the files and reports syntax errors
+//!                      and names that are declared in multiple files
+//! nexus test <file>    Runs the `test_` functions of the file
+//! nexus tokens <file>  Prints the tokens of the file
+//! nexus ast <file>     Prints the syntax tree of the file
+//! nexus debug <file>   Runs the file in the debugger
+//! nexus repl           Starts an interactive session
+//! ```
+//!
+//! See `meta_command` for the commands of the repl
+//! and `Prompt` for the commands of the debugger
+
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    panic::{self, AssertUnwindSafe},
+    process::ExitCode,
+};
+
+use nexus_lib::{
+    debugger::{DebugAction, DebugHandler, Debugger, Pause},
+    evaluator::{objects::Object, EvalConfig, Evaluator},
+    fmt,
+    lexer::Lexer,
+    parser::{
+        ast::{Program, Statement},
+        builder::{LinkError, ProgramBuilder},
+        parse_source, SyntaxError,
+    },
+    testing,
+};
+
+const USAGE: &str = "Usage: nexus <run|check|test|tokens|ast|debug> <file>
+       nexus run [--profile] <files...>
+       nexus run --coverage <file>
+       nexus check <files...>
+       nexus repl";
+
+const RUN_FLAGS: [&str; 2] = ["--profile", "--coverage"];
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["run", ref rest @ ..] => {
+            let (flags, files): (Vec<&str>, Vec<&str>) =
+                rest.iter().partition(|arg| arg.starts_with("--"));
+            let coverage = flags.contains(&"--coverage");
+            match files[..] {
+                [_, ..]
+                    if flags.iter().all(|flag| RUN_FLAGS.contains(flag))
+                        && (!coverage || files.len() == 1) =>
+                {
+                    run(&files, &flags)
+                }
+                _ => {
+                    eprintln!("{}", USAGE);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        ["check", ref files @ ..] if !files.is_empty() => check(files),
+        ["test", file] => test(file),
+        ["tokens", file] => tokens(file),
+        ["ast", file] => ast(file),
+        ["debug", file] => debug(file),
+        ["repl"] => repl(),
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(files: &[&str], flags: &[&str]) -> ExitCode {
+    let Some(program) = parse_files(files) else {
+        return ExitCode::FAILURE;
+    };
+    let mut evaluator = Evaluator::with_config(EvalConfig {
+        profile: flags.contains(&"--profile"),
+        coverage: flags.contains(&"--coverage"),
+        ..Default::default()
+    });
+    let obj = panic::catch_unwind(AssertUnwindSafe(|| evaluator.eval_program(program)));
+    for warning in evaluator.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    if let Some(report) = evaluator.profile_report() {
+        eprint!("{}", report);
+    }
+    if let Some(coverage) = evaluator.coverage() {
+        eprintln!("{}", coverage.summary());
+        if let Err(err) = fs::write("lcov.info", coverage.to_lcov(files[0])) {
+            eprintln!("error: Failed to write lcov.info: {}", err);
+        }
+    }
+    match obj {
+        Ok(Object::Err(err)) => {
+            eprintln!("error[{}]: {}", err.code().code(), err);
+            ExitCode::FAILURE
+        }
+        Ok(_) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    }
+}
+
+/// There is no type checker yet, so this only reports syntax errors
+fn check(files: &[&str]) -> ExitCode {
+    match parse_files(files) {
+        Some(_) => ExitCode::SUCCESS,
+        None => ExitCode::FAILURE,
+    }
+}
+
+fn test(file: &str) -> ExitCode {
+    let Some(program) = parse_file(file) else {
+        return ExitCode::FAILURE;
+    };
+    let report = panic::catch_unwind(|| testing::run_tests(program, EvalConfig::default()));
+    match report {
+        Ok(Ok(report)) => {
+            println!("{}", report);
+            match report.is_success() {
+                true => ExitCode::SUCCESS,
+                false => ExitCode::FAILURE,
+            }
+        }
+        Ok(Err(err)) => {
+            eprintln!("error[{}]: {}", err.code().code(), err);
+            ExitCode::FAILURE
+        }
+        Err(_) => ExitCode::FAILURE,
+    }
+}
+
+fn tokens(file: &str) -> ExitCode {
+    let Some(source) = read_file(file) else {
+        return ExitCode::FAILURE;
+    };
+    match print_tokens(&source) {
+        Some(()) => ExitCode::SUCCESS,
+        None => ExitCode::FAILURE,
+    }
+}
+
+/// Returns `None` if the source contains invalid tokens
+fn print_tokens(source: &str) -> Option<()> {
+    let tokens = Lexer::tokens(source).map_err(print_syntax_error).ok()?;
+    for tok in tokens {
+        println!("{:?}", tok);
+    }
+    Some(())
+}
+
+fn ast(file: &str) -> ExitCode {
+    match parse_file(file) {
+        Some(program) => {
+            println!("{:#?}", program);
+            ExitCode::SUCCESS
+        }
+        None => ExitCode::FAILURE,
+    }
+}
+
+fn debug(file: &str) -> ExitCode {
+    let Some(program) = parse_file(file) else {
+        return ExitCode::FAILURE;
+    };
+    println!("Paused before the first statement, type `help` for the commands");
+    let mut evaluator = Evaluator::new();
+    evaluator.set_observer(Box::new(Debugger::new(file, Prompt).pause_on_start()));
+    match panic::catch_unwind(AssertUnwindSafe(|| evaluator.eval_program(program))) {
+        Ok(Object::Err(err)) => {
+            eprintln!("error[{}]: {}", err.code().code(), err);
+            ExitCode::FAILURE
+        }
+        Ok(_) => ExitCode::SUCCESS,
+        Err(_) => ExitCode::FAILURE,
+    }
+}
+
+/// Asks the user what to do whenever the debugger pauses:
+///
+/// - `c`, `continue` runs until the next breakpoint
+/// - `s`, `step` pauses at the next statement
+/// - `n`, `next` pauses at the next statement, but skips over function calls
+/// - `b <line>`, `break <line>` adds a breakpoint
+/// - `d <line>`, `delete <line>` removes a breakpoint
+/// - `l`, `locals` prints the variables of all scopes
+/// - `p <name>`, `print <name>` prints a variable
+struct Prompt;
+
+impl DebugHandler for Prompt {
+    fn on_pause(&mut self, pause: &mut Pause<'_>) -> DebugAction {
+        let stmt = fmt::format_stmt(pause.stmt);
+        // Only the first line, blocks would be too long
+        println!(
+            "{}:{}: {}",
+            pause.file,
+            pause.line,
+            stmt.lines().next().unwrap_or("")
+        );
+        loop {
+            print!("(debug) ");
+            let mut line = String::new();
+            if io::stdout().flush().is_err()
+                || matches!(io::stdin().read_line(&mut line), Ok(0) | Err(_))
+            {
+                // Nobody can answer anymore, so run the rest of the program
+                return DebugAction::Continue;
+            }
+            let (command, arg) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            match command {
+                "c" | "continue" => return DebugAction::Continue,
+                "s" | "step" => return DebugAction::Step,
+                "n" | "next" => return DebugAction::StepOver,
+                "b" | "break" | "d" | "delete" => match arg.parse() {
+                    Ok(line) if command.starts_with('b') => {
+                        pause.breakpoints.insert(line);
+                    }
+                    Ok(line) => {
+                        pause.breakpoints.remove(&line);
+                    }
+                    Err(_) => eprintln!("error: Expected a line number"),
+                },
+                "l" | "locals" => {
+                    for (depth, scope) in pause.locals().iter().enumerate() {
+                        for (name, obj) in scope {
+                            println!("{}{} = {}", "  ".repeat(depth), name, obj.inspect());
+                        }
+                    }
+                }
+                "p" | "print" => match pause.get(arg) {
+                    Some(obj) => println!("{}", obj.inspect()),
+                    None => eprintln!("error: {} is not defined", arg),
+                },
+                _ => println!("Commands: continue, step, next, break <line>, delete <line>, locals, print <name>"),
+            }
+        }
+    }
+}
+
+fn repl() -> ExitCode {
+    let mut evaluator = Evaluator::new();
+    let stdin = io::stdin();
+    loop {
+        print!(">> ");
+        if io::stdout().flush().is_err() {
+            return ExitCode::FAILURE;
+        }
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            // End of the input, e.g. ctrl-d
+            Ok(0) => return ExitCode::SUCCESS,
+            Ok(_) => (),
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+        if let Some(command) = line.trim().strip_prefix(':') {
+            meta_command(&evaluator, command);
+            continue;
+        }
+        let Some(program) = parse(&line) else {
+            continue;
+        };
+        // Declarations are not echoed
+        let is_expr = matches!(program.stmts.last(), Some(Statement::Expression(_)));
+        // A line that fails is undone completely, including
+        // the declarations that ran before the error
+        let snapshot = evaluator.env.snapshot();
+        match panic::catch_unwind(AssertUnwindSafe(|| evaluator.eval_program(program))) {
+            Ok(Object::Err(err)) => {
+                eprintln!("error[{}]: {}", err.code().code(), err);
+                evaluator.env.restore(&snapshot);
+            }
+            Ok(Object::Void) => (),
+            Ok(obj) if is_expr => println!("{}", obj.inspect()),
+            Ok(_) => (),
+            Err(_) => evaluator.env.restore(&snapshot),
+        }
+    }
+}
+
+/// Commands of the repl that inspect code instead of running it:
+///
+/// - `:tokens <code>` prints the tokens of the code
+/// - `:ast <code>` prints the syntax tree of the code
+/// - `:env` prints all global variables
+/// - `:type <expr>` prints the type of the expression without evaluating it
+fn meta_command(evaluator: &Evaluator, command: &str) {
+    let (name, code) = command.split_once(' ').unwrap_or((command, ""));
+    match name {
+        "tokens" => {
+            print_tokens(code);
+        }
+        "ast" => {
+            if let Some(program) = parse(code) {
+                println!("{:#?}", program);
+            }
+        }
+        "env" => {
+            let mut globals: Vec<_> = evaluator.globals().collect();
+            globals.sort_by(|(left, _), (right, _)| left.cmp(right));
+            for (name, obj) in globals {
+                println!("{}: {} = {}", name, obj.type_name(), obj.inspect());
+            }
+        }
+        "type" => {
+            if let Some(program) = parse(code) {
+                match &program.stmts[..] {
+                    [Statement::Expression(expr)] => {
+                        println!("{}", evaluator.static_type(expr).unwrap_or("unknown"))
+                    }
+                    _ => eprintln!("error: :type expects a single expression"),
+                }
+            }
+        }
+        _ => eprintln!(
+            "error: Unknown command :{}, expected :tokens, :ast, :env or :type",
+            name
+        ),
+    }
+}
+
+fn read_file(file: &str) -> Option<String> {
+    match fs::read_to_string(file) {
+        Ok(source) => Some(source),
+        Err(err) => {
+            eprintln!("error: Failed to read {}: {}", file, err);
+            None
+        }
+    }
+}
+
+fn parse_file(file: &str) -> Option<Program> {
+    parse(&read_file(file)?)
+}
+
+/// Joins the files into one program, see `ProgramBuilder`.
+/// Returns `None` if a file contains syntax errors
+/// or declares the same name as another one
+fn parse_files(files: &[&str]) -> Option<Program> {
+    let mut builder = ProgramBuilder::new();
+    for file in files {
+        builder.add_source(*file, read_file(file)?);
+    }
+    match builder.build() {
+        Ok(linked) => Some(linked.program),
+        Err(LinkError::Syntax { err, location }) => {
+            print_syntax_error(err);
+            eprintln!("  at {}", location);
+            None
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            None
+        }
+    }
+}
+
+/// Returns `None` if the source contains syntax errors
+fn parse(source: &str) -> Option<Program> {
+    parse_source(source).map_err(print_syntax_error).ok()
+}
+
+fn print_syntax_error(err: SyntaxError) {
+    eprintln!("error[{}]: {}", err.code().code(), err);
+}
@@ -0,0 +1,168 @@
+//! Stable codes of all errors, so tooling and documentation can refer
+//! to an error without depending on its message.
+//!
+//! The first two digits are the category, see [`ErrorCategory`].
+//! Codes are never reused or renumbered, removed errors leave a gap
+
+mod tests;
+
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The lexer could not turn the source code into tokens
+    Lexical,
+    /// The tokens do not form a valid program
+    Syntax,
+    /// A name was declared twice or not at all
+    Name,
+    /// A value had the wrong type or shape for an operation
+    Type,
+    /// The program hit one of the limits of `EvalConfig`
+    Limit,
+    /// Threads or functions of the host failed, arithmetic was impossible
+    /// or the program used a feature that is not implemented yet
+    Runtime,
+    /// `assert` or `assert_eq` failed
+    Assertion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    InvalidCharacter,
+    InvalidNumber,
+    UnterminatedString,
+    /// Syntax errors that have no code of their own
+    InvalidSyntax,
+    UnexpectedToken,
+    MissingStatementEnd,
+    UnexpectedEof,
+    StackedLocal,
+    DanglingAnnotation,
+    InvalidAssignTarget,
+    Redeclaration,
+    ConstAssignment,
+    UndefinedVariable,
+    WrongArgCount,
+    UnknownField,
+    NotIndexable,
+    IndexOutOfBounds,
+    InvalidIndex,
+    InvalidSlice,
+    UnhashableKey,
+    Incomparable,
+    TypeMismatch,
+    NotCallable,
+    OutOfFuel,
+    Timeout,
+    OutOfMemory,
+    StackOverflow,
+    ThreadFailed,
+    HostFailed,
+    DivisionByZero,
+    Overflow,
+    Unsupported,
+    AssertionFailed,
+}
+
+impl ErrorCode {
+    /// Every code, ordered by their number
+    pub const ALL: [ErrorCode; 33] = [
+        ErrorCode::InvalidCharacter,
+        ErrorCode::InvalidNumber,
+        ErrorCode::UnterminatedString,
+        ErrorCode::InvalidSyntax,
+        ErrorCode::UnexpectedToken,
+        ErrorCode::MissingStatementEnd,
+        ErrorCode::UnexpectedEof,
+        ErrorCode::StackedLocal,
+        ErrorCode::DanglingAnnotation,
+        ErrorCode::InvalidAssignTarget,
+        ErrorCode::Redeclaration,
+        ErrorCode::ConstAssignment,
+        ErrorCode::UndefinedVariable,
+        ErrorCode::WrongArgCount,
+        ErrorCode::UnknownField,
+        ErrorCode::NotIndexable,
+        ErrorCode::IndexOutOfBounds,
+        ErrorCode::InvalidIndex,
+        ErrorCode::InvalidSlice,
+        ErrorCode::UnhashableKey,
+        ErrorCode::Incomparable,
+        ErrorCode::TypeMismatch,
+        ErrorCode::NotCallable,
+        ErrorCode::OutOfFuel,
+        ErrorCode::Timeout,
+        ErrorCode::OutOfMemory,
+        ErrorCode::StackOverflow,
+        ErrorCode::ThreadFailed,
+        ErrorCode::HostFailed,
+        ErrorCode::DivisionByZero,
+        ErrorCode::Overflow,
+        ErrorCode::Unsupported,
+        ErrorCode::AssertionFailed,
+    ];
+
+    /// The code as it is printed, e.g. `E0101`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidCharacter => "E0001",
+            ErrorCode::InvalidNumber => "E0002",
+            ErrorCode::UnterminatedString => "E0003",
+            ErrorCode::InvalidSyntax => "E0100",
+            ErrorCode::UnexpectedToken => "E0101",
+            ErrorCode::MissingStatementEnd => "E0102",
+            ErrorCode::UnexpectedEof => "E0103",
+            ErrorCode::StackedLocal => "E0104",
+            ErrorCode::DanglingAnnotation => "E0105",
+            ErrorCode::InvalidAssignTarget => "E0106",
+            ErrorCode::Redeclaration => "E0201",
+            ErrorCode::ConstAssignment => "E0202",
+            ErrorCode::UndefinedVariable => "E0203",
+            ErrorCode::WrongArgCount => "E0301",
+            ErrorCode::UnknownField => "E0302",
+            ErrorCode::NotIndexable => "E0303",
+            ErrorCode::IndexOutOfBounds => "E0304",
+            ErrorCode::InvalidIndex => "E0305",
+            ErrorCode::InvalidSlice => "E0306",
+            ErrorCode::UnhashableKey => "E0307",
+            ErrorCode::Incomparable => "E0308",
+            ErrorCode::TypeMismatch => "E0310",
+            ErrorCode::NotCallable => "E0311",
+            ErrorCode::OutOfFuel => "E0401",
+            ErrorCode::Timeout => "E0402",
+            ErrorCode::OutOfMemory => "E0403",
+            ErrorCode::StackOverflow => "E0404",
+            ErrorCode::ThreadFailed => "E0501",
+            ErrorCode::HostFailed => "E0502",
+            ErrorCode::DivisionByZero => "E0503",
+            ErrorCode::Overflow => "E0504",
+            ErrorCode::Unsupported => "E0505",
+            ErrorCode::AssertionFailed => "E0601",
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match &self.code()[1..3] {
+            "00" => ErrorCategory::Lexical,
+            "01" => ErrorCategory::Syntax,
+            "02" => ErrorCategory::Name,
+            "03" => ErrorCategory::Type,
+            "04" => ErrorCategory::Limit,
+            "05" => ErrorCategory::Runtime,
+            _ => ErrorCategory::Assertion,
+        }
+    }
+
+    /// Code with the given number, e.g. `E0101`
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|other| other.code() == code)
+    }
+}
+
+/// Prints the code and its name, e.g. `E0101 UnexpectedToken`
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:?}", self.code(), self)
+    }
+}
@@ -0,0 +1,27 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::codes::{ErrorCategory, ErrorCode};
+
+    /// Test for checking if every code is unique and can be looked up again
+    #[test]
+    fn test_codes() {
+        let codes: HashSet<&str> = ErrorCode::ALL.iter().map(ErrorCode::code).collect();
+        assert_eq!(ErrorCode::ALL.len(), codes.len());
+        for code in ErrorCode::ALL {
+            assert_eq!(Some(code), ErrorCode::from_code(code.code()));
+        }
+        let mut sorted = ErrorCode::ALL.map(|code| code.code());
+        sorted.sort();
+        assert_eq!(ErrorCode::ALL.map(|code| code.code()), sorted);
+
+        assert_eq!(
+            "E0101 UnexpectedToken",
+            ErrorCode::UnexpectedToken.to_string()
+        );
+        assert_eq!(ErrorCategory::Name, ErrorCode::UndefinedVariable.category());
+        assert_eq!(ErrorCategory::Type, ErrorCode::TypeMismatch.category());
+        assert_eq!(None, ErrorCode::from_code("E9999"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Debugger that pauses the evaluation at breakpoints or after each step.
+//!
+//! The [`Debugger`] is an [`EvalObserver`], so it gets installed with
+//! `Evaluator::set_observer`. Whenever the evaluation pauses, the
+//! [`DebugHandler`] decides how to continue. The handler can inspect the
+//! variables of all scopes and change the breakpoints in the meantime.
+//! It blocks the evaluation until it returns, e.g. while waiting for user input
+
+mod tests;
+
+use std::collections::BTreeSet;
+
+use crate::{
+    evaluator::{env::Environment, objects::Object, observer::EvalObserver},
+    parser::ast::Statement,
+};
+
+/// How the evaluation continues after a pause
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugAction {
+    /// Run until the next breakpoint
+    Continue,
+    /// Pause at the next statement, even inside of called functions
+    Step,
+    /// Pause at the next statement that is not nested deeper
+    /// than the current one, e.g. skip over function calls
+    StepOver,
+}
+
+pub trait DebugHandler: Send + Sync {
+    fn on_pause(&mut self, pause: &mut Pause<'_>) -> DebugAction;
+}
+
+/// State of the program while the evaluation is paused
+pub struct Pause<'a> {
+    pub file: &'a str,
+    /// Line of the statement that is evaluated next
+    pub line: usize,
+    pub stmt: &'a Statement,
+    /// Lines where the evaluation pauses
+    pub breakpoints: &'a mut BTreeSet<usize>,
+    env: &'a Environment,
+}
+
+impl Pause<'_> {
+    /// Variables of every scope, from the innermost to the global scope.
+    /// Each scope is sorted by name
+    pub fn locals(&self) -> Vec<Vec<(String, Object)>> {
+        self.env
+            .scopes()
+            .map(|scope| {
+                let mut vars: Vec<_> = scope
+                    .iter()
+                    .map(|(name, var)| (name.clone(), var.obj.clone()))
+                    .collect();
+                vars.sort_by(|(left, _), (right, _)| left.cmp(right));
+                vars
+            })
+            .collect()
+    }
+
+    /// Value of the variable that is visible at this point
+    pub fn get(&self, name: &str) -> Option<Object> {
+        self.env.get(&name.to_string()).map(|var| var.obj.clone())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Run,
+    Step,
+    /// Contains the depth of the environment at the last pause
+    StepOver(usize),
+}
+
+pub struct Debugger<H: DebugHandler> {
+    file: String,
+    breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    handler: H,
+}
+
+impl<H: DebugHandler> Debugger<H> {
+    /// Debugger for the program of the file.
+    /// It runs until the first breakpoint
+    pub fn new(file: impl Into<String>, handler: H) -> Self {
+        Self {
+            file: file.into(),
+            breakpoints: BTreeSet::new(),
+            mode: Mode::Run,
+            handler,
+        }
+    }
+
+    /// Pauses at the first statement of the program
+    pub fn pause_on_start(mut self) -> Self {
+        self.mode = Mode::Step;
+        self
+    }
+
+    /// Adds a breakpoint at `file:line`.
+    /// Breakpoints in other files than the one being debugged are ignored
+    pub fn add_breakpoint(&mut self, file: &str, line: usize) {
+        if file == self.file {
+            self.breakpoints.insert(line);
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, file: &str, line: usize) {
+        if file == self.file {
+            self.breakpoints.remove(&line);
+        }
+    }
+
+    fn should_pause(&self, line: usize, depth: usize) -> bool {
+        match self.mode {
+            Mode::Run => self.breakpoints.contains(&line),
+            Mode::Step => true,
+            Mode::StepOver(paused_depth) => {
+                depth <= paused_depth || self.breakpoints.contains(&line)
+            }
+        }
+    }
+}
+
+impl<H: DebugHandler> EvalObserver for Debugger<H> {
+    fn on_statement_enter(&mut self, stmt: &Statement, line: Option<usize>, env: &Environment) {
+        // Statements without a line cannot be matched against breakpoints
+        let Some(line) = line else {
+            return;
+        };
+        if !self.should_pause(line, env.depth()) {
+            return;
+        }
+        let mut pause = Pause {
+            file: &self.file,
+            line,
+            stmt,
+            breakpoints: &mut self.breakpoints,
+            env,
+        };
+        self.mode = match self.handler.on_pause(&mut pause) {
+            DebugAction::Continue => Mode::Run,
+            DebugAction::Step => Mode::Step,
+            DebugAction::StepOver => Mode::StepOver(env.depth()),
+        };
+    }
+}
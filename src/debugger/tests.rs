@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        debugger::{DebugAction, DebugHandler, Debugger, Pause},
+        evaluator::{objects::Object, Evaluator},
+        lexer::Lexer,
+        parser::{ast::Literal, Parser},
+    };
+
+    /// Line and value of `n` of every pause
+    type Pauses = Arc<Mutex<Vec<(usize, Option<Object>)>>>;
+
+    /// Handler that records where it paused
+    /// and answers with predefined actions
+    struct Script {
+        actions: Vec<DebugAction>,
+        pauses: Pauses,
+    }
+
+    impl DebugHandler for Script {
+        fn on_pause(&mut self, pause: &mut Pause<'_>) -> DebugAction {
+            self.pauses
+                .lock()
+                .unwrap()
+                .push((pause.line, pause.get("n")));
+            if pause.line == 2 {
+                assert_eq!(
+                    vec![("n".to_string(), Object::Lit(Literal::Num(1.0)))],
+                    pause.locals()[1]
+                );
+                assert!(pause.locals()[2].iter().any(|(name, _)| name == "x"));
+                pause.breakpoints.insert(7);
+            }
+            self.actions.remove(0)
+        }
+    }
+
+    /// Test for checking if the debugger pauses at breakpoints,
+    /// steps into and over functions and shows the variables
+    #[test]
+    fn test_debugger() {
+        let pauses = Arc::new(Mutex::new(Vec::new()));
+        let script = Script {
+            actions: vec![
+                DebugAction::Step,
+                DebugAction::Continue,
+                DebugAction::StepOver,
+            ],
+            pauses: Arc::clone(&pauses),
+        };
+        let mut debugger = Debugger::new("debug.nx", script);
+        debugger.add_breakpoint("debug.nx", 6);
+        debugger.add_breakpoint("other.nx", 1);
+
+        let mut lexer = Lexer::new(&"tests/debugger/debug.nx".into()).expect("Failed to find file");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        let mut evaluator = Evaluator::new();
+        evaluator.set_observer(Box::new(debugger));
+        evaluator.eval_program(program);
+
+        let num = |num| Some(Object::Lit(Literal::Num(num)));
+        assert_eq!(
+            vec![(6, None), (2, num(1.0)), (7, None)],
+            *pauses.lock().unwrap()
+        );
+        assert!(evaluator.take_observer().is_some());
+    }
+}
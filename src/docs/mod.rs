@@ -0,0 +1,81 @@
+//! Documentation of the declarations of a program.
+//!
+//! [`extract`] collects every declaration of the top level together with
+//! the `///` comments above it, e.g. to generate reference pages.
+//! Declarations without doc comments are included as well,
+//! so the pages can list everything a file defines
+
+mod tests;
+
+use crate::parser::ast::{Expression, Program, Statement, VarStmt};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocItem {
+    pub name: String,
+    pub kind: DocKind,
+    /// Type annotation of the declaration, e.g. `Num` for `const x: Num = 1`
+    pub _type: Option<String>,
+    /// Text of the doc comments, one line per comment
+    pub docs: Option<String>,
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DocKind {
+    Variable,
+    Constant,
+    /// Arguments are written like in the source, e.g. `n: Num`
+    Function {
+        args: Vec<String>,
+        ret_type: Option<String>,
+    },
+    Struct {
+        fields: Vec<String>,
+    },
+    Enum,
+}
+
+/// Documentation of every declaration of the top level, in source order.
+/// `local` declarations are included as well
+pub fn extract(program: &Program) -> Vec<DocItem> {
+    program
+        .stmts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, stmt)| {
+            let var = match stmt {
+                Statement::Variable(var) => var,
+                Statement::Local(local) => match local.val.as_ref() {
+                    Statement::Variable(var) => var,
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            Some(item(var, program.lines.get(i).copied()))
+        })
+        .collect()
+}
+
+fn item(var: &VarStmt, line: Option<usize>) -> DocItem {
+    let kind = match &var.val {
+        Expression::Func(func) => DocKind::Function {
+            args: func.args.iter().map(ToString::to_string).collect(),
+            ret_type: func.ret_type.as_ref().map(ToString::to_string),
+        },
+        Expression::Struct(_struct) => DocKind::Struct {
+            fields: _struct.fields.iter().map(ToString::to_string).collect(),
+        },
+        Expression::Enum(_) => DocKind::Enum,
+        _ if var.is_const => DocKind::Constant,
+        _ => DocKind::Variable,
+    };
+    DocItem {
+        name: var.name.ident.to_string(),
+        kind,
+        _type: var.name._type.as_ref().map(ToString::to_string),
+        docs: var.docs.clone(),
+        line,
+    }
+}
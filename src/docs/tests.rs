@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        docs::{extract, DocItem, DocKind},
+        parser::parse_source,
+    };
+
+    /// Test for checking if doc comments are attached to the
+    /// following declaration and only to declarations
+    #[test]
+    fn test_extract() {
+        let source = fs::read_to_string("tests/docs/docs.nx").expect("Failed to find file");
+        assert_eq!(
+            vec![
+                DocItem {
+                    name: "max".into(),
+                    kind: DocKind::Constant,
+                    _type: Some("Num".into()),
+                    docs: Some("Largest allowed size\n\nApplies to every list".into()),
+                    line: Some(5),
+                },
+                DocItem {
+                    name: "double".into(),
+                    kind: DocKind::Function {
+                        args: vec!["n: Num".into(), "factor".into()],
+                        ret_type: Some("Num".into()),
+                    },
+                    _type: None,
+                    docs: Some("Doubles n".into()),
+                    line: Some(8),
+                },
+                DocItem {
+                    name: "count".into(),
+                    kind: DocKind::Variable,
+                    _type: None,
+                    docs: None,
+                    line: Some(16),
+                },
+            ],
+            extract(&parse_source(&source).unwrap())
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Evaluation that can be driven by an async runtime.
+//!
+//! The evaluator yields back to the runtime after every top level statement.
+//! Calls to `sleep` do not block the thread in this mode,
+//! instead the evaluator waits asynchronously at the next statement boundary.
+//!
+//! `sleep` is the only builtin that waits asynchronously. `print` and `input`
+//! go through the synchronous `EvalIo` of the evaluator, so an io that
+//! blocks, like the default one reading stdin, also blocks the task
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{mpsc, OnceLock},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::parser::ast::Program;
+
+use super::{objects::Object, Evaluator};
+
+impl Evaluator {
+    /// Async version of [`Evaluator::eval_program`]
+    /// that yields between statements.
+    ///
+    /// Only top level statements are boundaries. A statement that calls a
+    /// long running function blocks the thread until the call returns.
+    /// Calls to `sleep` anywhere inside of a statement, including inside
+    /// of functions, only add up and are waited for once the whole
+    /// top level statement is done
+    pub async fn eval_program_async(&mut self, program: Program) -> Object {
+        self.start_program(&program);
+        let evaluator = AsyncMode::new(self);
+        let mut last = Object::Void;
+        let mut lines = program.lines.into_iter();
+        for stmt in program.stmts {
+            last = evaluator.0.eval_stmt_at(stmt, lines.next());
+            match evaluator.0.pending_sleep.take() {
+                Some(duration) => Sleep::new(duration).await,
+                None => YieldNow(false).await,
+            }
+            if let Object::Err(_) = last {
+                break;
+            }
+        }
+        last
+    }
+}
+
+/// Keeps the evaluator in async mode until the evaluation
+/// is done or its future is dropped
+struct AsyncMode<'a>(&'a mut Evaluator);
+
+impl<'a> AsyncMode<'a> {
+    fn new(evaluator: &'a mut Evaluator) -> Self {
+        evaluator.is_async = true;
+        Self(evaluator)
+    }
+}
+
+impl Drop for AsyncMode<'_> {
+    fn drop(&mut self) {
+        self.0.is_async = false;
+        self.0.pending_sleep = None;
+    }
+}
+
+/// Returns `Pending` exactly once so other tasks get a chance to run
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Timer that does not depend on a specific runtime.
+/// The thread of [`timer`] wakes the task once the time is up
+struct Sleep {
+    deadline: Instant,
+    /// Waker that was last passed to the timer
+    waker: Option<Waker>,
+}
+
+impl Sleep {
+    fn new(duration: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + duration,
+            waker: None,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        if !self
+            .waker
+            .as_ref()
+            .is_some_and(|waker| waker.will_wake(cx.waker()))
+        {
+            // The timer never stops, so sending cannot fail
+            let _ = timer().send((self.deadline, cx.waker().clone()));
+            self.waker = Some(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// One thread that wakes the tasks of all sleeps once their deadline
+/// passed, started by the first sleep. A waker that was replaced
+/// in the meantime gets woken as well, which is harmless
+fn timer() -> &'static mpsc::Sender<(Instant, Waker)> {
+    static TIMER: OnceLock<mpsc::Sender<(Instant, Waker)>> = OnceLock::new();
+    TIMER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || run_timer(receiver));
+        sender
+    })
+}
+
+fn run_timer(receiver: mpsc::Receiver<(Instant, Waker)>) {
+    let mut pending: Vec<(Instant, Waker)> = Vec::new();
+    loop {
+        let next = pending.iter().map(|(deadline, _)| *deadline).min();
+        let received = match next {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => receiver
+                .recv()
+                .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(sleep) => pending.push(sleep),
+            Err(mpsc::RecvTimeoutError::Timeout) => (),
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+        let now = Instant::now();
+        pending.retain(|(deadline, waker)| {
+            if *deadline > now {
+                return true;
+            }
+            waker.wake_by_ref();
+            false
+        });
+    }
+}
@@ -1,13 +1,86 @@
-use std::io;
+use std::{cmp::Ordering, fmt::Debug, sync::Arc};
 
 use crate::parser::ast::Literal;
 
-use super::objects::Object;
+use super::{
+    errors::EvalError,
+    io::EvalIo,
+    objects::{ListObj, Object, StrObj},
+};
+
+/// Names of all functions that are provided by the evaluator
+pub const BUILTIN_NAMES: [&str; 14] = [
+    "print",
+    "input",
+    "len",
+    "sleep",
+    "spawn",
+    "join",
+    "channel",
+    "send",
+    "recv",
+    "assert",
+    "assert_eq",
+    "compare",
+    "sort",
+    "sort_by",
+];
+
+/// Builtins that read input or write output
+const IO_BUILTINS: [&str; 2] = ["print", "input"];
+
+/// Builtins that only compute a value from their arguments
+const PURE_BUILTINS: [&str; 6] = ["len", "assert", "assert_eq", "compare", "sort", "sort_by"];
+
+/// Which builtins a program can call, see `EvalConfig::builtins`.
+///
+/// Builtins that are not allowed do not exist for the program,
+/// calling them is the same as calling an undeclared function.
+/// Functions registered by the host can do anything, so only
+/// `Full` and `Only` allow them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum BuiltinProfile {
+    /// Every builtin and every function of the host
+    #[default]
+    Full,
+    /// Every builtin except `print` and `input`
+    NoIo,
+    /// Only builtins without side effects: `len`, `assert`, `assert_eq`,
+    /// `compare`, `sort` and `sort_by`
+    Pure,
+    /// Only the builtins and functions of the host with these names
+    Only(Vec<String>),
+}
+
+impl BuiltinProfile {
+    pub fn allows(&self, name: &str) -> bool {
+        match self {
+            BuiltinProfile::Full => true,
+            BuiltinProfile::NoIo => BUILTIN_NAMES.contains(&name) && !IO_BUILTINS.contains(&name),
+            BuiltinProfile::Pure => PURE_BUILTINS.contains(&name),
+            BuiltinProfile::Only(names) => names.iter().any(|allowed| allowed == name),
+        }
+    }
+}
+
+/// Function that is provided by the host of the evaluator,
+/// see `Evaluator::register_builtin`
+#[derive(Clone)]
+pub struct HostFunc(pub Arc<HostFn>);
+
+pub type HostFn = dyn Fn(&[Object]) -> Result<Object, EvalError> + Send + Sync;
+
+impl Debug for HostFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HostFunc")
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum BuiltinFunc {
     Print(Print),
     Input(Input),
+    Len(Len),
 }
 
 impl BuiltinFunc {
@@ -15,6 +88,7 @@ impl BuiltinFunc {
         match self {
             BuiltinFunc::Print(_) => None,
             BuiltinFunc::Input(input) => Some(Object::Lit(input.ret_val.clone())),
+            BuiltinFunc::Len(len) => Some(Object::Lit(len.ret_val.clone())),
         }
     }
 }
@@ -28,20 +102,13 @@ pub struct Input {
 }
 
 impl Input {
-    pub fn new(print_val: Option<String>) -> Self {
-        let mut input = String::new();
-
+    /// Reads a line, which is empty if there is no input left
+    pub fn new(print_val: Option<String>, io: &dyn EvalIo) -> Self {
         if let Some(val) = print_val {
-            println!("{}", val)
-        }
-
-        // Read a line from the standard input and handle potential errors
-        match io::stdin().read_line(&mut input) {
-            Ok(_) => (),
-            Err(error) => eprintln!("Error reading input: {}", error),
+            io.print(&val);
         }
         Self {
-            ret_val: Literal::Str(input),
+            ret_val: Literal::Str(io.read_line().unwrap_or_default()),
         }
     }
 }
@@ -50,14 +117,107 @@ impl Input {
 pub struct Print;
 
 impl Print {
-    pub fn new(args: &Vec<Object>) -> Self {
-        let mut buf = String::new();
-        for arg in args {
-            buf.push_str(&arg.to_string());
-            buf.push(' ')
-        }
-        println!("{}", buf);
+    pub fn new(args: &[Object], io: &dyn EvalIo) -> Self {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        io.print(&args.join(" "));
 
         Self
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct Len {
+    // Always a number literal
+    ret_val: Literal,
+}
+
+impl Len {
+    /// Length of a string (in characters), list or hash
+    pub fn new(args: &[Object]) -> Result<Self, EvalError> {
+        let arg = match args {
+            [arg] => arg,
+            _ => {
+                return Err(EvalError::WrongArgCount {
+                    expected: 1,
+                    got: args.len(),
+                })
+            }
+        };
+        let len = match arg {
+            Object::Lit(Literal::Str(str)) => StrObj(str).len(),
+            Object::List(list) => list.list.len(),
+            Object::Hash(hash) => hash.map.len(),
+            other => {
+                return Err(EvalError::TypeMismatch {
+                    expected: "Str, List or Hash".into(),
+                    got: other.type_name().into(),
+                })
+            }
+        };
+        Ok(Self {
+            ret_val: Literal::Num(len as f64),
+        })
+    }
+}
+
+/// -1, 0 or 1 if the first value is less than, equal to or greater
+/// than the second one, in the order of `Object::compare`
+pub(super) fn builtin_compare(args: &[Object]) -> Result<Object, EvalError> {
+    let [left, right] = args else {
+        return Err(EvalError::WrongArgCount {
+            expected: 2,
+            got: args.len(),
+        });
+    };
+    let ordering = left.compare(right)? as i8;
+    Ok(Object::Lit(Literal::Num(ordering as f64)))
+}
+
+/// Sorted copy of the list, in the order of `Object::compare`
+pub(super) fn builtin_sort(args: &[Object]) -> Result<Object, EvalError> {
+    let list = match args {
+        [Object::List(list)] => list,
+        [other] => {
+            return Err(EvalError::TypeMismatch {
+                expected: "List".into(),
+                got: other.type_name().into(),
+            })
+        }
+        _ => {
+            return Err(EvalError::WrongArgCount {
+                expected: 1,
+                got: args.len(),
+            })
+        }
+    };
+    let list = sort_stable(list.list.clone(), &mut Object::compare)?;
+    Ok(Object::List(ListObj { list }))
+}
+
+/// Merge sort that keeps the order of equal elements
+/// and stops at the first comparison that fails.
+/// Unlike `slice::sort_by` it does not panic if the comparison
+/// is not a total order, which functions of programs cannot promise
+pub(super) fn sort_stable(
+    mut list: Vec<Object>,
+    cmp: &mut impl FnMut(&Object, &Object) -> Result<Ordering, EvalError>,
+) -> Result<Vec<Object>, EvalError> {
+    if list.len() <= 1 {
+        return Ok(list);
+    }
+    let right = list.split_off(list.len() / 2);
+    let mut left = sort_stable(list, cmp)?.into_iter().peekable();
+    let mut right = sort_stable(right, cmp)?.into_iter().peekable();
+    let mut sorted = Vec::with_capacity(left.len() + right.len());
+    while let (Some(first), Some(second)) = (left.peek(), right.peek()) {
+        // Taking from the left unless the right is less keeps equal elements in order
+        let next = match cmp(second, first)? {
+            Ordering::Less => right.next(),
+            _ => left.next(),
+        };
+        sorted.extend(next);
+    }
+    sorted.extend(left);
+    sorted.extend(right);
+    Ok(sorted)
+}
@@ -0,0 +1,128 @@
+//! Builtins for running functions on other threads
+//! and passing values between them.
+//!
+//! - `spawn(func, args...)` calls the function on a new thread and returns the thread.
+//!   The thread works on a copy of the current environment,
+//!   so it cannot change the variables of the spawning thread.
+//!   It uses up the same fuel and memory as the spawning thread and has
+//!   the same deadline, so spawning cannot be used to escape the limits
+//!   of `EvalConfig`
+//! - `join(thread)` waits for the thread and returns the value of the function
+//! - `channel()` creates a channel that can be shared between threads
+//! - `send(channel, value)` sends the value over the channel
+//! - `recv(channel)` waits for the next value of the channel
+
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use super::{
+    errors::EvalError,
+    objects::{ChannelObj, Object, ThreadObj},
+    Evaluator,
+};
+
+impl Evaluator {
+    pub(super) fn builtin_spawn(&self, args: Vec<Object>) -> Result<Object, EvalError> {
+        let mut args = args.into_iter();
+        let func = match args.next() {
+            Some(Object::Func(func)) => func,
+            Some(other) => {
+                return Err(EvalError::TypeMismatch {
+                    expected: "Func".into(),
+                    got: other.type_name().into(),
+                })
+            }
+            None => {
+                return Err(EvalError::WrongArgCount {
+                    expected: 1,
+                    got: 0,
+                })
+            }
+        };
+        let args: Vec<Object> = args.collect();
+
+        let mut evaluator = Evaluator::with_config(self.config.clone());
+        evaluator.fuel = self.fuel.clone();
+        evaluator.deadline = self.deadline;
+        evaluator.allocated = Arc::clone(&self.allocated);
+        evaluator.env = self.env.clone();
+        evaluator.io = Arc::clone(&self.io);
+        evaluator.host_funcs = self.host_funcs.clone();
+        // Fails on platforms without threads, like wasm32-unknown-unknown
+        let handle = thread::Builder::new()
+            // Not `call_function`, which would start a new count of the allocations
+            .spawn(move || evaluator.call_func(&func, args))
+            .map_err(|err| EvalError::ThreadFailed(format!("Failed to spawn a thread: {}", err)))?;
+        Ok(Object::Thread(ThreadObj {
+            handle: Arc::new(Mutex::new(Some(handle))),
+        }))
+    }
+}
+
+pub(super) fn builtin_join(args: &[Object]) -> Result<Object, EvalError> {
+    let thread = match args {
+        [Object::Thread(thread)] => thread,
+        [other] => return Err(type_mismatch("Thread", other)),
+        _ => return Err(wrong_arg_count(1, args.len())),
+    };
+    let handle = lock(&thread.handle)
+        .take()
+        .ok_or_else(|| EvalError::ThreadFailed("The thread was already joined".into()))?;
+    handle
+        .join()
+        .map_err(|_| EvalError::ThreadFailed("The thread panicked".into()))
+}
+
+pub(super) fn builtin_channel(args: &[Object]) -> Result<Object, EvalError> {
+    if !args.is_empty() {
+        return Err(wrong_arg_count(0, args.len()));
+    }
+    let (sender, receiver) = mpsc::channel();
+    Ok(Object::Channel(ChannelObj {
+        sender,
+        receiver: Arc::new(Mutex::new(receiver)),
+    }))
+}
+
+pub(super) fn builtin_send(args: Vec<Object>) -> Result<Object, EvalError> {
+    let [channel, val]: [Object; 2] = args
+        .try_into()
+        .map_err(|args: Vec<Object>| wrong_arg_count(2, args.len()))?;
+    match channel {
+        // The channel object owns a receiver itself,
+        // so sending cannot fail
+        Object::Channel(channel) => {
+            let _ = channel.sender.send(val);
+            Ok(Object::Void)
+        }
+        other => Err(type_mismatch("Channel", &other)),
+    }
+}
+
+/// Returns `none` if the channel cannot receive any values anymore
+pub(super) fn builtin_recv(args: &[Object]) -> Result<Object, EvalError> {
+    let channel = match args {
+        [Object::Channel(channel)] => channel,
+        [other] => return Err(type_mismatch("Channel", other)),
+        _ => return Err(wrong_arg_count(1, args.len())),
+    };
+    Ok(lock(&channel.receiver).recv().unwrap_or(Object::None))
+}
+
+/// Locks the mutex, even if another thread panicked while holding it
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn type_mismatch(expected: &str, got: &Object) -> EvalError {
+    EvalError::TypeMismatch {
+        expected: expected.into(),
+        got: got.type_name().into(),
+    }
+}
+
+fn wrong_arg_count(expected: usize, got: usize) -> EvalError {
+    EvalError::WrongArgCount { expected, got }
+}
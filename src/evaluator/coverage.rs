@@ -0,0 +1,166 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::parser::{
+    ast::{BlockStmt, Expression, Program, Statement},
+    visit::{self, Visitor},
+};
+
+/// Records which lines and branches ran.
+/// Enabled with `EvalConfig::coverage`.
+///
+/// Nodes only know the line they start on, so a branch is identified
+/// by the line of the statement containing the `if` or ternary and
+/// the index of the branch. The `else` branch always comes last,
+/// even if the `if` has none. Conditionals on the same line share
+/// their counters
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Coverage {
+    /// Hits of every line that starts a statement
+    lines: BTreeMap<usize, usize>,
+    /// Hits of every branch by line and index
+    branches: BTreeMap<(usize, usize), usize>,
+}
+
+impl Coverage {
+    /// Registers all statements and branches of the program,
+    /// so the ones that never run show up with zero hits
+    pub(super) fn instrument(&mut self, program: &Program) {
+        Instrumenter {
+            coverage: self,
+            line: None,
+        }
+        .visit_program(program);
+    }
+
+    pub(super) fn hit_line(&mut self, line: usize) {
+        *self.lines.entry(line).or_default() += 1;
+    }
+
+    pub(super) fn hit_branch(&mut self, line: usize, branch: usize) {
+        *self.branches.entry((line, branch)).or_default() += 1;
+    }
+
+    /// Hits of every statement line
+    pub fn lines(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.lines.iter().map(|(line, hits)| (*line, *hits))
+    }
+
+    /// Hits of every branch as `(line, branch, hits)`
+    pub fn branches(&self) -> impl Iterator<Item = (usize, usize, usize)> + '_ {
+        self.branches
+            .iter()
+            .map(|((line, branch), hits)| (*line, *branch, *hits))
+    }
+
+    pub fn summary(&self) -> CoverageSummary {
+        CoverageSummary {
+            lines_hit: self.lines.values().filter(|hits| **hits > 0).count(),
+            lines_total: self.lines.len(),
+            branches_hit: self.branches.values().filter(|hits| **hits > 0).count(),
+            branches_total: self.branches.len(),
+        }
+    }
+
+    /// Report in the lcov tracefile format, which is understood
+    /// by tools like `genhtml`. `file` is the path of the source file
+    pub fn to_lcov(&self, file: &str) -> String {
+        let summary = self.summary();
+        let mut lcov = format!("TN:\nSF:{}\n", file);
+        for (line, branch, hits) in self.branches() {
+            lcov.push_str(&format!("BRDA:{},0,{},{}\n", line, branch, hits));
+        }
+        lcov.push_str(&format!(
+            "BRF:{}\nBRH:{}\n",
+            summary.branches_total, summary.branches_hit
+        ));
+        for (line, hits) in self.lines() {
+            lcov.push_str(&format!("DA:{},{}\n", line, hits));
+        }
+        lcov.push_str(&format!(
+            "LF:{}\nLH:{}\nend_of_record\n",
+            summary.lines_total, summary.lines_hit
+        ));
+        lcov
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoverageSummary {
+    pub lines_hit: usize,
+    pub lines_total: usize,
+    pub branches_hit: usize,
+    pub branches_total: usize,
+}
+
+impl Display for CoverageSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "lines:    {}/{} ({})",
+            self.lines_hit,
+            self.lines_total,
+            percent(self.lines_hit, self.lines_total)
+        )?;
+        write!(
+            f,
+            "branches: {}/{} ({})",
+            self.branches_hit,
+            self.branches_total,
+            percent(self.branches_hit, self.branches_total)
+        )
+    }
+}
+
+fn percent(hit: usize, total: usize) -> String {
+    match total {
+        0 => "-".into(),
+        _ => format!("{:.1}%", hit as f64 * 100.0 / total as f64),
+    }
+}
+
+struct Instrumenter<'a> {
+    coverage: &'a mut Coverage,
+    /// Line of the statement that is currently visited
+    line: Option<usize>,
+}
+
+impl Instrumenter<'_> {
+    fn visit_lines(&mut self, stmts: &[Statement], lines: &[usize]) {
+        for (i, stmt) in stmts.iter().enumerate() {
+            let prev = self.line;
+            self.line = lines.get(i).copied().or(prev);
+            if let Some(line) = lines.get(i) {
+                self.coverage.lines.entry(*line).or_default();
+            }
+            self.visit_stmt(stmt);
+            self.line = prev;
+        }
+    }
+
+    fn add_branches(&mut self, count: usize) {
+        if let Some(line) = self.line {
+            for branch in 0..count {
+                self.coverage.branches.entry((line, branch)).or_default();
+            }
+        }
+    }
+}
+
+impl Visitor for Instrumenter<'_> {
+    fn visit_program(&mut self, program: &Program) {
+        self.visit_lines(&program.stmts, &program.lines);
+    }
+
+    fn visit_block(&mut self, block: &BlockStmt) {
+        self.visit_lines(&block.stmts, &block.lines);
+    }
+
+    fn visit_expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::If(_if) => self.add_branches(_if.branches.len() + 1),
+            Expression::Ternary(_) => self.add_branches(2),
+            _ => (),
+        }
+        visit::walk_expr(self, expr);
+    }
+}
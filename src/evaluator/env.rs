@@ -1,13 +1,25 @@
-use std::{collections::HashMap, ops::{Deref, DerefMut}};
+use std::{collections::HashMap, sync::Arc};
 
-use super::objects::Object;
+use super::{errors::EvalError, objects::Object};
 
-#[derive(Debug)]
+/// Variables of all scopes that are currently active.
+/// The first scope is the global scope,
+/// the last one is the innermost scope.
+///
+/// Scopes are shared with snapshots and clones of the environment
+/// and only copied once one of them changes, see [`Environment::snapshot`]
+#[derive(Debug, Clone)]
 pub struct Environment {
-    store: HashMap<String, EnvObj>,
+    scopes: Vec<Arc<HashMap<String, EnvObj>>>,
 }
 
-#[derive(Debug)]
+/// State of an environment that it can be reset to
+#[derive(Debug, Clone)]
+pub struct EnvSnapshot {
+    scopes: Vec<Arc<HashMap<String, EnvObj>>>,
+}
+
+#[derive(Debug, Clone)]
 pub struct EnvObj {
     pub obj: Object,
     pub is_const: bool,
@@ -28,15 +40,110 @@ impl Default for Environment {
 impl Environment {
     pub fn new() -> Self {
         Self {
-            store: HashMap::new(),
+            scopes: vec![Arc::default()],
+        }
+    }
+
+    /// Enters a new scope inside of the current one
+    pub fn push_scope(&mut self) {
+        self.scopes.push(Arc::default());
+    }
+
+    /// Leaves the innermost scope and drops its variables.
+    /// The global scope is never removed
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
         }
     }
 
     pub fn set(&mut self, key: String, obj: EnvObj) {
-        self.store.insert(key, obj);
+        self.innermost().insert(key, obj);
     }
 
+    /// Declares a variable in the innermost scope.
+    ///
+    /// Redeclaring a constant is always an error.
+    /// Redeclaring a variable replaces it, unless `strict` is set
+    pub fn declare(&mut self, key: String, obj: EnvObj, strict: bool) -> Result<(), EvalError> {
+        let scope = self.innermost();
+        if let Some(old) = scope.get(&key) {
+            if strict || old.is_const {
+                return Err(EvalError::Redeclaration(key));
+            }
+        }
+        scope.insert(key, obj);
+        Ok(())
+    }
+
+    /// Changes the value of a variable in the innermost scope that declares it.
+    /// Constants cannot be changed. Undeclared variables have no suggestion,
+    /// since builtins are not part of the environment
+    pub fn assign(&mut self, key: String, obj: Object) -> Result<(), EvalError> {
+        let Some(scope) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.contains_key(&key))
+        else {
+            return Err(EvalError::UndefinedVariable {
+                name: key,
+                suggestion: None,
+            });
+        };
+        if scope[&key].is_const {
+            return Err(EvalError::ConstAssignment(key));
+        }
+        Arc::make_mut(scope)
+            .get_mut(&key)
+            .expect("The scope contains the variable")
+            .obj = obj;
+        Ok(())
+    }
+
+    /// Searches all scopes from the innermost to the global scope
     pub fn get(&self, key: &String) -> Option<&EnvObj> {
-        self.store.get(key)
+        self.scopes.iter().rev().find_map(|scope| scope.get(key))
+    }
+
+    /// Saves the current state without copying any variables.
+    /// A scope is copied the first time it changes after the snapshot,
+    /// so taking a snapshot before every evaluation is cheap
+    pub fn snapshot(&self) -> EnvSnapshot {
+        EnvSnapshot {
+            scopes: self.scopes.clone(),
+        }
+    }
+
+    /// Resets the variables and scopes to the state of the snapshot.
+    /// The snapshot stays valid and can be restored again
+    pub fn restore(&mut self, snapshot: &EnvSnapshot) {
+        self.scopes = snapshot.scopes.clone();
+    }
+
+    /// Variables declared in the global scope
+    pub fn globals(&self) -> impl Iterator<Item = (&String, &Object)> {
+        self.scopes[0].iter().map(|(name, var)| (name, &var.obj))
+    }
+
+    /// Amount of scopes, the global scope has a depth of 1
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Variables of every scope, from the innermost to the global scope
+    pub fn scopes(&self) -> impl Iterator<Item = &HashMap<String, EnvObj>> {
+        self.scopes.iter().rev().map(|scope| &**scope)
+    }
+
+    /// Whether declaring `key` in the innermost scope
+    /// would shadow a variable of an outer scope
+    pub fn is_shadowing(&self, key: &String) -> bool {
+        let (innermost, outer) = self.scopes.split_last().expect("There is always a scope");
+        !innermost.contains_key(key) && outer.iter().any(|scope| scope.contains_key(key))
+    }
+
+    fn innermost(&mut self) -> &mut HashMap<String, EnvObj> {
+        Arc::make_mut(self.scopes.last_mut().expect("There is always a scope"))
     }
 }
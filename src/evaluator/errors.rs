@@ -0,0 +1,241 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{codes::ErrorCode, parser::SyntaxError};
+
+/// Errors that can occur while evaluating a program.
+/// They are passed around as `Object::Err` and abort
+/// the evaluation of the surrounding expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    /// A value that cannot be hashed was used as a key of a hash.
+    /// Contains the type of the value
+    UnhashableKey(String),
+    /// Tried to index a value that does not support indexing.
+    /// Contains the type of the value
+    NotIndexable(String),
+    /// The index was out of bounds
+    IndexOutOfBounds { index: i64, len: usize },
+    /// The index was not a whole number
+    InvalidIndex(String),
+    /// The start of a slice was greater than its end
+    InvalidSlice { start: i64, end: i64 },
+    /// A value had a different type than expected
+    TypeMismatch { expected: String, got: String },
+    /// A value that is not a function was called.
+    /// Contains the type of the value
+    NotCallable(String),
+    /// Two values cannot be ordered, see `Object::compare`.
+    /// Contains the types of both values
+    Incomparable { left: String, right: String },
+    /// The value does not have a field with this name
+    UnknownField { _type: String, field: String },
+    /// A variable was declared twice in the same scope
+    Redeclaration(String),
+    /// A constant was assigned a new value
+    ConstAssignment(String),
+    /// A variable or function was used without being declared.
+    /// Contains the declared name that is closest to it, if there is one
+    UndefinedVariable {
+        name: String,
+        suggestion: Option<String>,
+    },
+    /// A function was called with the wrong amount of arguments
+    WrongArgCount { expected: usize, got: usize },
+    /// The evaluation used up all of its fuel
+    OutOfFuel,
+    /// The evaluation took longer than the configured timeout
+    Timeout,
+    /// The program allocated more memory than the limit allows.
+    /// Contains the limit in bytes
+    OutOfMemory { limit: usize },
+    /// Function calls were nested deeper than the limit allows.
+    /// Contains the limit
+    StackOverflow { limit: usize },
+    /// A thread could not be joined
+    ThreadFailed(String),
+    /// A function registered by the host failed.
+    /// Contains the message of the host
+    HostFailed(String),
+    /// A decimal was divided by zero
+    DivisionByZero,
+    /// A number does not fit into the type it was turned into, like the
+    /// result of a decimal operation or the duration of `sleep`.
+    /// Contains the type
+    Overflow(String),
+    /// The program used a feature of the language that
+    /// cannot be evaluated yet. Contains the name of the feature
+    Unsupported(String),
+    /// `assert` or `assert_eq` failed.
+    /// Contains the line of the statement if it is known
+    AssertionFailed { msg: String, line: Option<usize> },
+}
+
+impl EvalError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            EvalError::UnhashableKey(_) => ErrorCode::UnhashableKey,
+            EvalError::NotIndexable(_) => ErrorCode::NotIndexable,
+            EvalError::IndexOutOfBounds { .. } => ErrorCode::IndexOutOfBounds,
+            EvalError::InvalidIndex(_) => ErrorCode::InvalidIndex,
+            EvalError::InvalidSlice { .. } => ErrorCode::InvalidSlice,
+            EvalError::TypeMismatch { .. } => ErrorCode::TypeMismatch,
+            EvalError::NotCallable(_) => ErrorCode::NotCallable,
+            EvalError::Incomparable { .. } => ErrorCode::Incomparable,
+            EvalError::UnknownField { .. } => ErrorCode::UnknownField,
+            EvalError::Redeclaration(_) => ErrorCode::Redeclaration,
+            EvalError::ConstAssignment(_) => ErrorCode::ConstAssignment,
+            EvalError::UndefinedVariable { .. } => ErrorCode::UndefinedVariable,
+            EvalError::WrongArgCount { .. } => ErrorCode::WrongArgCount,
+            EvalError::OutOfFuel => ErrorCode::OutOfFuel,
+            EvalError::Timeout => ErrorCode::Timeout,
+            EvalError::OutOfMemory { .. } => ErrorCode::OutOfMemory,
+            EvalError::StackOverflow { .. } => ErrorCode::StackOverflow,
+            EvalError::ThreadFailed(_) => ErrorCode::ThreadFailed,
+            EvalError::HostFailed(_) => ErrorCode::HostFailed,
+            EvalError::DivisionByZero => ErrorCode::DivisionByZero,
+            EvalError::Overflow(_) => ErrorCode::Overflow,
+            EvalError::Unsupported(_) => ErrorCode::Unsupported,
+            EvalError::AssertionFailed { .. } => ErrorCode::AssertionFailed,
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::UnhashableKey(_type) => {
+                write!(f, "Cannot use a value of type {} as a hash key", _type)
+            }
+            EvalError::NotIndexable(_type) => write!(f, "Cannot index a value of type {}", _type),
+            EvalError::IndexOutOfBounds { index, len } => write!(
+                f,
+                "Index {} is out of bounds for a sequence of length {}",
+                index, len
+            ),
+            EvalError::InvalidIndex(index) => {
+                write!(f, "Index {} is not a whole number", index)
+            }
+            EvalError::InvalidSlice { start, end } => write!(
+                f,
+                "Start of the slice {} is greater than its end {}",
+                start, end
+            ),
+            EvalError::TypeMismatch { expected, got } => {
+                write!(f, "Expected a value of type {}, got {}", expected, got)
+            }
+            EvalError::NotCallable(_type) => write!(f, "Cannot call a value of type {}", _type),
+            EvalError::Incomparable { left, right } => write!(
+                f,
+                "Cannot compare a value of type {} with a value of type {}",
+                left, right
+            ),
+            EvalError::UnknownField { _type, field } => {
+                write!(f, "Value of type {} has no field {}", _type, field)
+            }
+            EvalError::Redeclaration(name) => {
+                write!(f, "{} is already declared in this scope", name)
+            }
+            EvalError::ConstAssignment(name) => write!(f, "Cannot assign to the constant {}", name),
+            EvalError::UndefinedVariable {
+                name,
+                suggestion: None,
+            } => write!(f, "{} is not declared", name),
+            EvalError::UndefinedVariable {
+                name,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "{} is not declared, did you mean '{}'?",
+                name, suggestion
+            ),
+            EvalError::WrongArgCount { expected, got } => write!(
+                f,
+                "Expected {} arguments, but {} were provided",
+                expected, got
+            ),
+            EvalError::OutOfFuel => write!(f, "Ran out of fuel before the evaluation finished"),
+            EvalError::Timeout => write!(f, "The evaluation took longer than allowed"),
+            EvalError::ThreadFailed(msg) => write!(f, "{}", msg),
+            EvalError::HostFailed(msg) => write!(f, "{}", msg),
+            EvalError::DivisionByZero => write!(f, "Cannot divide a decimal by zero"),
+            EvalError::Overflow(_type) => write!(f, "The number does not fit into a {}", _type),
+            EvalError::Unsupported(feature) => write!(f, "{} are not supported yet", feature),
+            EvalError::OutOfMemory { limit } => {
+                write!(f, "The program allocated more than {} bytes", limit)
+            }
+            EvalError::StackOverflow { limit } => {
+                write!(f, "Function calls were nested deeper than {}", limit)
+            }
+            EvalError::AssertionFailed {
+                msg,
+                line: Some(line),
+            } => {
+                write!(f, "Assertion failed on line {}: {}", line, msg)
+            }
+            EvalError::AssertionFailed { msg, line: None } => {
+                write!(f, "Assertion failed: {}", msg)
+            }
+        }
+    }
+}
+
+/// Error of source code that is parsed and evaluated in one go,
+/// see `Evaluator::eval_expression_source`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceError {
+    Syntax(SyntaxError),
+    Eval(EvalError),
+}
+
+impl SourceError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            SourceError::Syntax(err) => err.code(),
+            SourceError::Eval(err) => err.code(),
+        }
+    }
+}
+
+impl From<SyntaxError> for SourceError {
+    fn from(err: SyntaxError) -> Self {
+        SourceError::Syntax(err)
+    }
+}
+
+impl From<EvalError> for SourceError {
+    fn from(err: EvalError) -> Self {
+        SourceError::Eval(err)
+    }
+}
+
+impl Error for SourceError {}
+
+impl Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceError::Syntax(err) => write!(f, "{}", err),
+            SourceError::Eval(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Problems that do not stop the evaluation,
+/// but likely are mistakes
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalWarning {
+    /// A variable was declared with the same
+    /// name as a variable of an outer scope
+    Shadowing(String),
+}
+
+impl Display for EvalWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalWarning::Shadowing(name) => {
+                write!(f, "{} shadows a variable of an outer scope", name)
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Input and output of the `print` and `input` builtins.
+//!
+//! By default they use stdin and stdout. Hosts without them, like a
+//! browser, or hosts that want to capture the output use another
+//! [`EvalIo`] with `Evaluator::set_io`. Threads started with `spawn`
+//! share the io of the evaluator that started them
+
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io::{self, Write},
+    sync::Mutex,
+};
+
+pub trait EvalIo: Send + Sync {
+    /// Writes a line of output, `text` does not end with a line break
+    fn print(&self, text: &str);
+
+    /// Reads a line of input including its line break.
+    /// `None` if there is no input left or it cannot be read
+    fn read_line(&self) -> Option<String>;
+}
+
+impl Debug for dyn EvalIo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EvalIo")
+    }
+}
+
+/// Uses stdin and stdout of the process
+#[derive(Debug, Default)]
+pub struct StdIo;
+
+impl EvalIo for StdIo {
+    fn print(&self, text: &str) {
+        let mut stdout = io::stdout().lock();
+        // Nothing can be reported if stdout is gone
+        let _ = writeln!(stdout, "{}", text);
+    }
+
+    fn read_line(&self) -> Option<String> {
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line),
+        }
+    }
+}
+
+/// Collects the output in memory and reads predefined lines of input
+#[derive(Debug, Default)]
+pub struct BufferedIo {
+    output: Mutex<String>,
+    input: Mutex<VecDeque<String>>,
+}
+
+impl BufferedIo {
+    pub fn new(input: &[&str]) -> Self {
+        Self {
+            output: Mutex::default(),
+            input: Mutex::new(input.iter().map(|line| format!("{}\n", line)).collect()),
+        }
+    }
+
+    /// Removes and returns everything that was printed so far
+    pub fn take_output(&self) -> String {
+        std::mem::take(&mut *lock(&self.output))
+    }
+}
+
+impl EvalIo for BufferedIo {
+    fn print(&self, text: &str) {
+        let mut output = lock(&self.output);
+        output.push_str(text);
+        output.push('\n');
+    }
+
+    fn read_line(&self) -> Option<String> {
+        lock(&self.input).pop_front()
+    }
+}
+
+/// The data stays valid even if a thread panicked while printing
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
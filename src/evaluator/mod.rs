@@ -1,25 +1,152 @@
 use std::{
-    cell::{RefCell, RefMut},
-    f32::consts::E,
-    rc::Rc,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use crate::parser::ast::{
-    BlockStmt, CallExpr, Expression, FuncExpr, Ident, InfixExpr, InfixOp, Literal, PrefixExpr,
-    PrefixOp, Statement, VarStmt,
+use crate::{
+    parser::{
+        ast::{
+            AssignExpr, BlockStmt, CallExpr, Expression, FieldExpr, FuncExpr, HashExpr, Ident,
+            IfExpr, IndexExpr, InfixExpr, InfixOp, ListExpr, Literal, PrefixExpr, PrefixOp,
+            Program, SliceExpr, Statement, TernaryExpr, VarStmt,
+        },
+        Parser,
+    },
+    util::{self, decimal::Decimal},
 };
-use builtins::{BuiltinFunc, Input, Print};
+use builtins::{BuiltinFunc, BuiltinProfile, HostFunc, Input, Len, Print, BUILTIN_NAMES};
+use coverage::Coverage;
 use env::{EnvObj, Environment};
-use objects::{Comparable, FuncObj, Object};
+use errors::{EvalError, EvalWarning, SourceError};
+use io::{EvalIo, StdIo};
+use objects::{FuncObj, HashKey, HashObj, ListObj, Object, StrObj};
+use observer::EvalObserver;
+use profiler::{ProfileReport, Profiler};
 
+#[cfg(feature = "async")]
+mod async_eval;
 pub mod builtins;
+mod concurrency;
+pub mod coverage;
 pub mod env;
+pub mod errors;
+pub mod io;
 pub mod objects;
+pub mod observer;
+pub mod profiler;
 mod tests;
+mod types;
+
+/// Whether the platform has a clock and can sleep.
+/// On wasm32-unknown-unknown timeouts are ignored,
+/// `sleep` returns immediately and profiling is disabled
+/// Default of `EvalConfig::max_call_depth`. Every call of the program
+/// takes several nested calls of the evaluator, so this is low enough
+/// for the 2 MiB stack of threads spawned by the standard library,
+/// even in debug builds
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 50;
+
+const HAS_CLOCK: bool = !cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+
+/// Returns the error from the current function
+/// if the object is an `Object::Err`
+macro_rules! propagate_err {
+    ($obj:expr) => {
+        match $obj {
+            Object::Err(err) => return Object::Err(err),
+            obj => obj,
+        }
+    };
+}
 
 #[derive(Debug)]
 pub struct Evaluator {
-    pub env: Rc<RefCell<Environment>>,
+    pub env: Environment,
+    pub config: EvalConfig,
+    warnings: Vec<EvalWarning>,
+    /// Steps left before the evaluation is aborted.
+    /// Shared with the threads spawned by the evaluation
+    fuel: Option<Arc<AtomicU64>>,
+    /// Point in time after which the evaluation is aborted
+    deadline: Option<Instant>,
+    /// Amount of steps evaluated so far
+    steps: u64,
+    timed_out: bool,
+    /// Approximate amount of bytes allocated since the evaluation started.
+    /// Shared with the threads spawned by the evaluation
+    allocated: Arc<AtomicUsize>,
+    /// Amount of function calls that have not returned yet
+    call_depth: usize,
+    /// Whether the evaluator is driven by `eval_program_async`
+    is_async: bool,
+    /// Time that `sleep` still needs to wait in async mode
+    pending_sleep: Option<Duration>,
+    observer: Option<Box<dyn EvalObserver>>,
+    io: Arc<dyn EvalIo>,
+    /// Functions registered with `register_builtin`
+    host_funcs: HashMap<String, HostFunc>,
+    profiler: Option<Profiler>,
+    coverage: Option<Coverage>,
+    /// Line of the statement that is currently evaluated
+    cur_line: Option<usize>,
+    /// Whether the error of the current statement
+    /// was already passed to the observer
+    error_reported: bool,
+}
+
+/// Options that change how programs are evaluated
+#[derive(Debug, Clone)]
+pub struct EvalConfig {
+    /// Declaring a variable twice in the same scope is an error
+    pub strict: bool,
+    /// Maximum amount of statements and expressions that are
+    /// evaluated before aborting with `EvalError::OutOfFuel`,
+    /// including the ones of spawned threads.
+    /// `None` means that there is no limit
+    pub fuel: Option<u64>,
+    /// Maximum time the evaluation may take before aborting
+    /// with `EvalError::Timeout`, measured from the creation of
+    /// the evaluator or the last call to `Evaluator::reset_deadline`
+    pub timeout: Option<Duration>,
+    /// Maximum amount of bytes that lists, hashes and slices
+    /// may allocate in total, including spawned threads,
+    /// before aborting with `EvalError::OutOfMemory`.
+    /// The sizes are estimated with `Object::approx_size`. The count starts
+    /// again with every call of `Evaluator::eval_program`,
+    /// `Evaluator::call_function` or `Evaluator::eval_expression_source`
+    pub memory_limit: Option<usize>,
+    /// Maximum amount of nested function calls before aborting with
+    /// `EvalError::StackOverflow`, so deep recursion fails before the
+    /// native stack overflows. [`DEFAULT_MAX_CALL_DEPTH`] by default
+    pub max_call_depth: usize,
+    /// Records the time and count of every function and line,
+    /// see `Evaluator::profile_report`
+    pub profile: bool,
+    /// Records which lines and branches ran,
+    /// see `Evaluator::coverage`
+    pub coverage: bool,
+    /// Builtins that programs can call, all of them by default
+    pub builtins: BuiltinProfile,
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            fuel: None,
+            timeout: None,
+            memory_limit: None,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            profile: false,
+            coverage: false,
+            builtins: BuiltinProfile::default(),
+        }
+    }
 }
 
 impl Default for Evaluator {
@@ -30,52 +157,534 @@ impl Default for Evaluator {
 
 impl Evaluator {
     pub fn new() -> Self {
+        Self::with_config(EvalConfig::default())
+    }
+
+    pub fn with_config(config: EvalConfig) -> Self {
         Self {
-            env: Rc::new(RefCell::new(Environment::new())),
+            env: Environment::new(),
+            fuel: Self::fuel(&config),
+            deadline: Self::deadline(&config),
+            steps: 0,
+            timed_out: false,
+            allocated: Arc::default(),
+            call_depth: 0,
+            is_async: false,
+            pending_sleep: None,
+            observer: None,
+            io: Arc::new(StdIo),
+            host_funcs: HashMap::new(),
+            profiler: (config.profile && HAS_CLOCK).then(Profiler::default),
+            coverage: config.coverage.then(Coverage::default),
+            cur_line: None,
+            error_reported: false,
+            config,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Steps that can still be evaluated or `None` if there is no limit
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.fuel.as_ref().map(|fuel| fuel.load(Ordering::Relaxed))
+    }
+
+    /// Resets the budget to the configured amount of fuel.
+    /// Threads that are still running keep the old budget
+    pub fn refuel(&mut self) {
+        self.fuel = Self::fuel(&self.config);
+    }
+
+    fn fuel(config: &EvalConfig) -> Option<Arc<AtomicU64>> {
+        config.fuel.map(|fuel| Arc::new(AtomicU64::new(fuel)))
+    }
+
+    /// Starts the configured timeout again from now
+    pub fn reset_deadline(&mut self) {
+        self.deadline = Self::deadline(&self.config);
+        self.timed_out = false;
+    }
+
+    fn deadline(config: &EvalConfig) -> Option<Instant> {
+        config
+            .timeout
+            .filter(|_| HAS_CLOCK)
+            .map(|timeout| Instant::now() + timeout)
+    }
+
+    /// Approximate amount of bytes allocated by the current
+    /// or last evaluation, see `EvalConfig::memory_limit`
+    pub fn allocated(&self) -> usize {
+        self.allocated.load(Ordering::Relaxed)
+    }
+
+    /// Accounts for a newly created object and returns it,
+    /// or an error if the memory limit would be exceeded
+    fn alloc(&mut self, obj: Object) -> Object {
+        match self.charge(obj.approx_size()) {
+            Ok(()) => obj,
+            Err(err) => Object::Err(err),
+        }
+    }
+
+    /// Accounts for the amount of bytes, unless the
+    /// memory limit would be exceeded
+    fn charge(&mut self, size: usize) -> Result<(), EvalError> {
+        let limit = self.config.memory_limit;
+        let res = self
+            .allocated
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |allocated| {
+                Some(allocated.saturating_add(size))
+                    .filter(|allocated| limit.is_none_or(|limit| *allocated <= limit))
+            });
+        match (res, limit) {
+            (Err(_), Some(limit)) => Err(EvalError::OutOfMemory { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Uses up one step of the budget and checks the deadline
+    /// every `DEADLINE_INTERVAL` steps.
+    /// Once a limit is reached every further step fails,
+    /// so the error reaches the top even if it gets discarded somewhere
+    fn step(&mut self) -> Result<(), EvalError> {
+        const DEADLINE_INTERVAL: u64 = 256;
+
+        if let Some(fuel) = &self.fuel {
+            let res = fuel.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |fuel| {
+                fuel.checked_sub(1)
+            });
+            if res.is_err() {
+                return Err(EvalError::OutOfFuel);
+            }
+        }
+
+        if self.timed_out {
+            return Err(EvalError::Timeout);
+        }
+        self.steps += 1;
+        if let Some(deadline) = self.deadline {
+            if self.steps.is_multiple_of(DEADLINE_INTERVAL) && Instant::now() >= deadline {
+                self.timed_out = true;
+                return Err(EvalError::Timeout);
+            }
+        }
+        Ok(())
+    }
+
+    /// Installs an observer that gets notified about the evaluation,
+    /// replacing the previous one
+    pub fn set_observer(&mut self, observer: Box<dyn EvalObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Removes the observer and returns it
+    pub fn take_observer(&mut self) -> Option<Box<dyn EvalObserver>> {
+        self.observer.take()
+    }
+
+    /// Replaces where `print` writes to and `input` reads from
+    pub fn set_io(&mut self, io: Arc<dyn EvalIo>) {
+        self.io = io;
+    }
+
+    /// Makes a function of the host callable by the program.
+    /// Builtins of the evaluator cannot be replaced, but a host
+    /// function hides a function of the program with the same name
+    pub fn register_builtin(
+        &mut self,
+        name: impl Into<String>,
+        func: impl Fn(&[Object]) -> Result<Object, EvalError> + Send + Sync + 'static,
+    ) {
+        self.host_funcs
+            .insert(name.into(), HostFunc(Arc::new(func)));
+    }
+
+    /// Times and counts of all functions and lines that ran so far,
+    /// or `None` if profiling is not enabled
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        self.profiler.as_ref().map(Profiler::report)
+    }
+
+    /// Lines and branches that ran so far,
+    /// or `None` if coverage is not enabled
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Warnings that were collected during evaluation
+    pub fn warnings(&self) -> &[EvalWarning] {
+        &self.warnings
+    }
+
+    /// Evaluates all statements of the program and returns the value
+    /// of the last one. Stops at the first error
+    pub fn eval_program(&mut self, program: Program) -> Object {
+        self.start_program(&program);
+        let mut last = Object::Void;
+        let mut lines = program.lines.into_iter();
+        for stmt in program.stmts {
+            last = self.eval_stmt_at(stmt, lines.next());
+            if let Object::Err(_) = last {
+                break;
+            }
+        }
+        last
+    }
+
+    /// Starts counting the allocations again and
+    /// prepares the coverage for the lines of the program
+    fn start_program(&mut self, program: &Program) {
+        self.allocated = Arc::default();
+        self.call_depth = 0;
+        if let Some(coverage) = &mut self.coverage {
+            coverage.instrument(program);
+        }
+    }
+
+    /// Evaluates source code that consists of exactly one expression,
+    /// like a formula in a config file, with the variables, configuration
+    /// and host functions of the evaluator. The variables are not changed,
+    /// even if the expression assigns to them
+    pub fn eval_expression_source(&mut self, source: &str) -> Result<Object, SourceError> {
+        let expr = Parser::parse_single_expression(source)?;
+        self.allocated = Arc::default();
+        self.call_depth = 0;
+        let snapshot = self.env.snapshot();
+        let obj = self.eval_expr(expr);
+        self.env.restore(&snapshot);
+        match obj {
+            Object::Err(err) => Err(err.into()),
+            obj => Ok(obj),
         }
     }
 
+    /// Variables and functions of the global scope,
+    /// e.g. everything a program defined at the top level
+    pub fn globals(&self) -> impl Iterator<Item = (String, Object)> + '_ {
+        self.env
+            .globals()
+            .map(|(name, obj)| (name.clone(), obj.clone()))
+    }
+
+    /// Looks up a function of the global scope,
+    /// which can then be run with [`Evaluator::call_function`]
+    pub fn get_function(&self, name: &str) -> Option<FuncObj> {
+        Self::get_func(self.env.get(&name.to_string())?.obj.clone())
+    }
+
     pub fn eval_stmt(&mut self, stmt: Statement) -> Object {
+        self.eval_stmt_at(stmt, None)
+    }
+
+    /// Evaluates a statement of which the line is known
+    /// (if it comes from the parser), so observers can see it
+    fn eval_stmt_at(&mut self, stmt: Statement, line: Option<usize>) -> Object {
+        let Some(line) = line else {
+            return self.eval_stmt_observed(stmt, None);
+        };
+        if let Some(coverage) = &mut self.coverage {
+            coverage.hit_line(line);
+        }
+        let prev_line = self.cur_line.replace(line);
+        let start = self
+            .profiler
+            .as_mut()
+            .map(|profiler| profiler.enter_line(line));
+        let obj = self.eval_stmt_observed(stmt, Some(line));
+        if let (Some(profiler), Some(start)) = (&mut self.profiler, start) {
+            profiler.exit_line(line, start);
+        }
+        self.cur_line = prev_line;
+        obj
+    }
+
+    /// Counts the branch of the current conditional if coverage is enabled
+    fn hit_branch(&mut self, branch: usize) {
+        if let (Some(coverage), Some(line)) = (&mut self.coverage, self.cur_line) {
+            coverage.hit_branch(line, branch);
+        }
+    }
+
+    fn eval_stmt_observed(&mut self, stmt: Statement, line: Option<usize>) -> Object {
+        if self.observer.is_none() {
+            return self.eval_stmt_unobserved(stmt);
+        }
+        if let Some(observer) = &mut self.observer {
+            observer.on_statement_enter(&stmt, line, &self.env);
+        }
+        self.error_reported = false;
+        let obj = self.eval_stmt_unobserved(stmt);
+        if let Some(observer) = &mut self.observer {
+            if let Object::Err(err) = &obj {
+                if !self.error_reported {
+                    observer.on_error(err);
+                    self.error_reported = true;
+                }
+            }
+            observer.on_statement_exit(&obj);
+        }
+        obj
+    }
+
+    fn eval_stmt_unobserved(&mut self, stmt: Statement) -> Object {
+        if let Err(err) = self.step() {
+            return Object::Err(err);
+        }
         match stmt {
             Statement::Variable(node) => self.eval_var(node),
-            Statement::Return(_) => todo!(),
-            Statement::Break(_) => todo!(),
-            Statement::Local(_) => todo!(),
-            Statement::Use(_) => todo!(),
+            Statement::Return(_) => Object::Err(EvalError::Unsupported("Return statements".into())),
+            Statement::Break(_) => Object::Err(EvalError::Unsupported("Break statements".into())),
+            Statement::Local(_) => Object::Err(EvalError::Unsupported("Local statements".into())),
+            Statement::Use(_) => Object::Err(EvalError::Unsupported("Use statements".into())),
             Statement::Expression(node) => self.eval_expr(node),
         }
     }
 
     fn eval_expr(&mut self, expr: Expression) -> Object {
+        if let Err(err) = self.step() {
+            return Object::Err(err);
+        }
         match expr {
             Expression::Ident(node) => self.eval_ident(node),
             Expression::Literal(node) => Object::Lit(node),
             Expression::Prefix(node) => self.eval_prefix(node),
             Expression::Infix(node) => self.eval_infix(node),
-            Expression::Index(_) => todo!(),
+            Expression::Assign(node) => self.eval_assign(node),
+            Expression::Ternary(node) => self.eval_ternary(node),
+            Expression::Index(node) => self.eval_index(node),
+            Expression::Field(node) => self.eval_field(node),
+            Expression::Slice(node) => self.eval_slice(node),
             Expression::Call(node) => self.eval_call(node),
-            Expression::List(_) => todo!(),
-            Expression::None => todo!(),
+            Expression::List(node) => self.eval_list(node),
+            Expression::Hash(node) => self.eval_hash(node),
+            Expression::None => Object::None,
             Expression::If(node) => self.eval_if(node),
-            Expression::Loop(_) => todo!(),
-            Expression::When(_) => todo!(),
+            Expression::Loop(_) => Object::Err(EvalError::Unsupported("Loops".into())),
+            Expression::When(_) => Object::Err(EvalError::Unsupported("When expressions".into())),
             Expression::Func(node) => self.eval_func(node),
-            Expression::Annotation(_) => todo!(),
-            Expression::Struct(_) => todo!(),
-            Expression::Enum(_) => todo!(),
+            Expression::Block(node) => self.eval_block(node).unwrap_or(Object::Void),
+            // Annotations do not change how the code runs
+            Expression::Annotation(node) => self.eval_expr(*node.val),
+            Expression::Struct(_) => Object::Err(EvalError::Unsupported("Structs".into())),
+            Expression::Enum(_) => Object::Err(EvalError::Unsupported("Enums".into())),
         }
     }
 
     fn eval_var(&mut self, node: VarStmt) -> Object {
-        let val = self.eval_expr(node.val);
-        self.env.borrow_mut().set(
-            node.name.ident.0.clone(),
-            EnvObj {
-                obj: val.clone(),
-                is_const: node.is_const,
+        let val = propagate_err!(self.eval_expr(node.val));
+        let name = node.name.ident.0;
+        if self.env.is_shadowing(&name) {
+            self.warnings.push(EvalWarning::Shadowing(name.clone()));
+        }
+        match self.env.declare(
+            name,
+            EnvObj::new(val.clone(), node.is_const),
+            self.config.strict,
+        ) {
+            Ok(()) => val,
+            Err(err) => Object::Err(err),
+        }
+    }
+
+    /// Elements and fields are changed on a copy of the variable
+    /// they belong to, which then replaces the variable. Only the
+    /// amount the variable grows counts towards `EvalConfig::memory_limit`.
+    /// The value is evaluated first, then the indices from left to right
+    fn eval_assign(&mut self, node: AssignExpr) -> Object {
+        let val = propagate_err!(self.eval_expr(*node.val));
+        let mut path = Vec::new();
+        let mut target = *node.target;
+        let name = loop {
+            match target {
+                Expression::Ident(ident) => break ident.0,
+                Expression::Index(index) => {
+                    path.push(Place::Index(*index.index));
+                    target = *index.list;
+                }
+                Expression::Field(field) => {
+                    path.push(Place::Field(field.field.0));
+                    target = *field.obj;
+                }
+                _ => unreachable!("The parser only allows variables, elements and fields"),
+            }
+        };
+        let mut places = Vec::new();
+        for place in path.into_iter().rev() {
+            places.push(match place {
+                Place::Index(index) => Place::Index(propagate_err!(self.eval_expr(index))),
+                Place::Field(field) => Place::Field(field),
+            });
+        }
+        let updated = match self.env.get(&name) {
+            Some(var) if !places.is_empty() => {
+                let mut root = var.obj.clone();
+                let before = root.approx_size();
+                if let Err(err) = Self::assign_place(&mut root, &places, val.clone()) {
+                    return Object::Err(err);
+                }
+                if let Err(err) = self.charge(root.approx_size().saturating_sub(before)) {
+                    return Object::Err(err);
+                }
+                root
+            }
+            _ => val.clone(),
+        };
+        match self.env.assign(name, updated) {
+            Ok(()) => val,
+            Err(EvalError::UndefinedVariable { name, .. }) => Object::Err(self.undefined(name)),
+            Err(err) => Object::Err(err),
+        }
+    }
+
+    /// Replaces the element or field at the end of the path
+    fn assign_place(obj: &mut Object, path: &[Place], val: Object) -> Result<(), EvalError> {
+        let Some((place, rest)) = path.split_first() else {
+            *obj = val;
+            return Ok(());
+        };
+        let slot = match (obj, place) {
+            (Object::List(list), Place::Index(index)) => {
+                let index = Self::conv_to_index(index.clone())?;
+                let pos = Self::resolve_index(index, list.list.len(), false)?;
+                &mut list.list[pos]
+            }
+            (Object::Hash(hash), Place::Index(key)) => hash
+                .map
+                .entry(HashKey::try_from(key)?)
+                .or_insert(Object::None),
+            (Object::Hash(hash), Place::Field(field)) => hash
+                .map
+                .entry(HashKey::Str(field.clone()))
+                .or_insert(Object::None),
+            (Object::Lit(Literal::Str(_)), Place::Index(_)) => {
+                return Err(EvalError::TypeMismatch {
+                    expected: "List or Hash".into(),
+                    got: "Str".into(),
+                })
+            }
+            (other, Place::Index(_)) => {
+                return Err(EvalError::NotIndexable(other.type_name().into()))
+            }
+            (other, Place::Field(field)) => {
+                return Err(EvalError::UnknownField {
+                    _type: other.type_name().into(),
+                    field: field.clone(),
+                })
+            }
+        };
+        Self::assign_place(slot, rest, val)
+    }
+
+    fn eval_list(&mut self, node: ListExpr) -> Object {
+        match self.eval_args(node.list) {
+            Ok(list) => self.alloc(Object::List(ListObj { list })),
+            Err(err) => Object::Err(err),
+        }
+    }
+
+    fn eval_hash(&mut self, node: HashExpr) -> Object {
+        let mut map = HashMap::new();
+        for (key, val) in node.pairs {
+            let key = propagate_err!(self.eval_expr(key));
+            let key = match HashKey::try_from(&key) {
+                Ok(key) => key,
+                Err(err) => return Object::Err(err),
+            };
+            let val = propagate_err!(self.eval_expr(val));
+            map.insert(key, val);
+        }
+        self.alloc(Object::Hash(HashObj { map }))
+    }
+
+    fn eval_index(&mut self, node: IndexExpr) -> Object {
+        let list = propagate_err!(self.eval_expr(*node.list));
+        if node.optional && list == Object::None {
+            return Object::None;
+        }
+        let index = propagate_err!(self.eval_expr(*node.index));
+        match list {
+            Object::List(list) => {
+                match Self::conv_to_index(index)
+                    .and_then(|index| Self::resolve_index(index, list.list.len(), false))
+                {
+                    Ok(index) => list.list[index].clone(),
+                    Err(err) => Object::Err(err),
+                }
+            }
+            Object::Lit(Literal::Str(str)) => {
+                let str = StrObj(&str);
+                match Self::conv_to_index(index)
+                    .and_then(|index| Self::resolve_index(index, str.len(), false))
+                {
+                    Ok(index) => Object::Lit(Literal::Str(str.get(index).unwrap())),
+                    Err(err) => Object::Err(err),
+                }
+            }
+            Object::Hash(hash) => match HashKey::try_from(&index) {
+                Ok(key) => hash.map.get(&key).cloned().unwrap_or(Object::None),
+                Err(err) => Object::Err(err),
             },
-        );
-        val
+            other => Object::Err(EvalError::NotIndexable(other.type_name().into())),
+        }
+    }
+
+    /// Fields of hashes are their string keys,
+    /// so `hash.name` is the same as `hash["name"]`
+    fn eval_field(&mut self, node: FieldExpr) -> Object {
+        let obj = propagate_err!(self.eval_expr(*node.obj));
+        match obj {
+            Object::None if node.optional => Object::None,
+            Object::Hash(hash) => hash
+                .map
+                .get(&HashKey::Str(node.field.0))
+                .cloned()
+                .unwrap_or(Object::None),
+            other => Object::Err(EvalError::UnknownField {
+                _type: other.type_name().into(),
+                field: node.field.0,
+            }),
+        }
+    }
+
+    fn eval_slice(&mut self, node: SliceExpr) -> Object {
+        let list = propagate_err!(self.eval_expr(*node.list));
+        if node.optional && list == Object::None {
+            return Object::None;
+        }
+        let len = match &list {
+            Object::List(list) => list.list.len(),
+            Object::Lit(Literal::Str(str)) => StrObj(str).len(),
+            other => return Object::Err(EvalError::NotIndexable(other.type_name().into())),
+        };
+        let mut bounds = [0, len];
+        for (bound, expr) in bounds.iter_mut().zip([node.start, node.end]) {
+            if let Some(expr) = expr {
+                let index = propagate_err!(self.eval_expr(*expr));
+                *bound = match Self::conv_to_index(index)
+                    .and_then(|index| Self::resolve_index(index, len, true))
+                {
+                    Ok(index) => index,
+                    Err(err) => return Object::Err(err),
+                };
+            }
+        }
+        let [start, end] = bounds;
+        if start > end {
+            return Object::Err(EvalError::InvalidSlice {
+                start: start as i64,
+                end: end as i64,
+            });
+        }
+        let slice = match list {
+            Object::List(list) => Object::List(ListObj {
+                list: list.list[start..end].to_vec(),
+            }),
+            Object::Lit(Literal::Str(str)) => {
+                Object::Lit(Literal::Str(StrObj(&str).slice(start, end)))
+            }
+            _ => unreachable!(),
+        };
+        self.alloc(slice)
     }
 
     fn eval_func(&mut self, node: FuncExpr) -> Object {
@@ -88,82 +697,318 @@ impl Evaluator {
     fn eval_call(&mut self, node: CallExpr) -> Object {
         let name = match *node.ident {
             Expression::Ident(ident) => ident.0.clone(),
-            _ => panic!("The function name is not an identifier"),
+            // Calls of values that are not named, like `f()()`
+            other => {
+                let func = propagate_err!(self.eval_expr(other));
+                let args = match self.eval_args(node.args) {
+                    Ok(args) => args,
+                    Err(err) => return Object::Err(err),
+                };
+                return match func {
+                    Object::Func(func) => self.call_func(&func, args),
+                    other => Object::Err(EvalError::NotCallable(other.type_name().into())),
+                };
+            }
         };
 
-        match name.as_str() {
-            "print" => {
-                Object::BuiltinFunc(BuiltinFunc::Print(Print::new(&self.eval_args(node.args))))
+        let args = match self.eval_args(node.args) {
+            Ok(args) => args,
+            Err(err) => return Object::Err(err),
+        };
+        if let Some(observer) = &mut self.observer {
+            observer.on_call(&name, &args);
+        }
+        let start = self
+            .profiler
+            .as_mut()
+            .map(|profiler| profiler.enter_function(&name));
+        let ret = self.call_by_name(&name, args);
+        if let (Some(profiler), Some(start)) = (&mut self.profiler, start) {
+            profiler.exit_function(&name, start);
+        }
+        if let Some(observer) = &mut self.observer {
+            observer.on_return(&name, &ret);
+        }
+        ret
+    }
+
+    fn call_by_name(&mut self, name: &str, args: Vec<Object>) -> Object {
+        match name {
+            _ if !self.config.builtins.allows(name) => self.call_program_func(name, args),
+            "print" => Object::BuiltinFunc(BuiltinFunc::Print(Print::new(&args, &*self.io))),
+            "input" => Self::builtin_ret_val(BuiltinFunc::Input(Input::new(None, &*self.io))),
+            "len" => match Len::new(&args) {
+                Ok(len) => Self::builtin_ret_val(BuiltinFunc::Len(len)),
+                Err(err) => Object::Err(err),
+            },
+            "sleep" => self.builtin_sleep(&args).unwrap_or_else(Object::Err),
+            "spawn" => self.builtin_spawn(args).unwrap_or_else(Object::Err),
+            "join" => concurrency::builtin_join(&args).unwrap_or_else(Object::Err),
+            "channel" => concurrency::builtin_channel(&args).unwrap_or_else(Object::Err),
+            "send" => concurrency::builtin_send(args).unwrap_or_else(Object::Err),
+            "recv" => concurrency::builtin_recv(&args).unwrap_or_else(Object::Err),
+            "assert" => self.builtin_assert(&args).unwrap_or_else(Object::Err),
+            "assert_eq" => self.builtin_assert_eq(&args).unwrap_or_else(Object::Err),
+            "compare" => builtins::builtin_compare(&args).unwrap_or_else(Object::Err),
+            "sort" => builtins::builtin_sort(&args)
+                .map(|list| self.alloc(list))
+                .unwrap_or_else(Object::Err),
+            "sort_by" => self.builtin_sort_by(&args).unwrap_or_else(Object::Err),
+            _ if self.host_funcs.contains_key(name) => {
+                let func = self.host_funcs[name].clone();
+                (func.0)(&args).unwrap_or_else(Object::Err)
             }
-            "input" => Object::BuiltinFunc(BuiltinFunc::Input(Input::new(None))),
-            _ => {
-                let old_env = Rc::clone(&self.env);
+            _ => self.call_program_func(name, args),
+        }
+    }
 
-                let mut call_args = Vec::new();
+    /// Error for a name that is neither declared nor a builtin the program
+    /// can call. Suggests the closest name of a variable of any scope,
+    /// a builtin or a function of the host
+    fn undefined(&self, name: String) -> EvalError {
+        let variables = self.env.scopes().flat_map(|scope| scope.keys());
+        let callable = BUILTIN_NAMES
+            .into_iter()
+            .chain(self.host_funcs.keys().map(String::as_str))
+            .filter(|builtin| self.config.builtins.allows(builtin));
+        let suggestion = util::closest_match(&name, variables.map(String::as_str).chain(callable))
+            .map(String::from);
+        EvalError::UndefinedVariable { name, suggestion }
+    }
 
-                let call_arg_len = node.args.len();
+    /// Calls a function that was declared by the program
+    fn call_program_func(&mut self, name: &str, args: Vec<Object>) -> Object {
+        let Some(func_obj) = self.env.get(&name.to_string()).cloned() else {
+            return Object::Err(self.undefined(name.into()));
+        };
+        match func_obj.obj {
+            Object::Func(func) => self.call_func(&func, args),
+            other => Object::Err(EvalError::NotCallable(other.type_name().into())),
+        }
+    }
 
-                for arg in node.args {
-                    call_args.push(self.eval_expr(arg));
-                }
+    /// Calls the function with already evaluated arguments.
+    /// The arguments are bound in a new scope inside of the current one
+    pub fn call_function(&mut self, func: &FuncObj, args: Vec<Object>) -> Object {
+        self.allocated = Arc::default();
+        self.call_depth = 0;
+        self.call_func(func, args)
+    }
 
-                // Get the function and add arguments to self.env
-                let func = {
-                    let mut env = self.env.borrow_mut();
+    /// Calls from inside of the evaluation, which keep
+    /// counting the allocations, see [`Evaluator::call_function`]
+    fn call_func(&mut self, func: &FuncObj, args: Vec<Object>) -> Object {
+        if func.args.len() != args.len() {
+            return Object::Err(EvalError::WrongArgCount {
+                expected: func.args.len(),
+                got: args.len(),
+            });
+        }
+        if self.call_depth >= self.config.max_call_depth {
+            return Object::Err(EvalError::StackOverflow {
+                limit: self.config.max_call_depth,
+            });
+        }
 
-                    let func_obj = env.get(&name).unwrap_or_else(|| {
-                        panic!("Failed to find a function with the name {}", &name)
-                    });
+        self.env.push_scope();
+        for (arg, call_arg) in func.args.iter().zip(args) {
+            self.env
+                .set(arg.ident.0.clone(), EnvObj::new(call_arg, false));
+        }
 
-                    let func = Self::get_func(func_obj.obj.clone()).unwrap_or_else(|| {
-                        panic!("Failed to find a function with the name {}", &name)
-                    });
+        self.call_depth += 1;
+        let last = self.eval_block(func.block.clone());
+        self.call_depth -= 1;
 
-                    if func.args.len() != call_arg_len {
-                        panic!("Amount of expected args: {}, does not match amount of provided args: {} for function: {}", func.args.len(), call_arg_len, name)
-                    }
+        self.env.pop_scope();
+        last.unwrap_or(Object::Void)
+    }
 
-                    for (arg, call_arg) in func.args.clone().into_iter().zip(call_args) {
-                        env.set(
-                            arg.ident.0,
-                            EnvObj::new(call_arg, false),
-                        );
-                    }
+    /// Waits for the amount of milliseconds.
+    /// In async mode the waiting happens at the end
+    /// of the statement without blocking the thread.
+    /// Sleeping past the deadline only waits until the
+    /// deadline and then fails with `EvalError::Timeout`
+    fn builtin_sleep(&mut self, args: &[Object]) -> Result<Object, EvalError> {
+        let millis = match args {
+            [Object::Lit(Literal::Num(millis))] if *millis >= 0.0 => *millis,
+            [other] => {
+                return Err(EvalError::TypeMismatch {
+                    expected: "positive Num".into(),
+                    got: other.type_name().into(),
+                })
+            }
+            _ => {
+                return Err(EvalError::WrongArgCount {
+                    expected: 1,
+                    got: args.len(),
+                })
+            }
+        };
+        let duration = Duration::try_from_secs_f64(millis / 1000.0)
+            .map_err(|_| EvalError::Overflow("Duration".into()))?;
+        let left = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        let (duration, timed_out) = match left {
+            Some(left) if duration > left => (left, true),
+            _ => (duration, false),
+        };
+        if self.is_async {
+            self.pending_sleep = Some(self.pending_sleep.unwrap_or_default() + duration);
+        } else if HAS_CLOCK {
+            thread::sleep(duration);
+        }
+        if timed_out {
+            self.timed_out = true;
+            return Err(EvalError::Timeout);
+        }
+        Ok(Object::Void)
+    }
 
-                    func
-                };
+    /// Fails if the condition is not truthy.
+    /// The optional second argument is used as the message
+    fn builtin_assert(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let (cond, msg) = match args {
+            [cond] => (cond, "condition is not truthy".to_string()),
+            [cond, msg] => (cond, msg.to_string()),
+            _ => {
+                return Err(EvalError::WrongArgCount {
+                    expected: 1,
+                    got: args.len(),
+                })
+            }
+        };
+        match Self::is_truthy(cond) {
+            true => Ok(Object::Void),
+            false => Err(self.assertion_failed(msg)),
+        }
+    }
 
-                let last = self.eval_block(func.block);
+    /// Fails if both values are not equal
+    fn builtin_assert_eq(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match args {
+            [left, right] if left == right => Ok(Object::Void),
+            [left, right] => Err(self.assertion_failed(format!(
+                "{} is not equal to {}",
+                left.inspect(),
+                right.inspect()
+            ))),
+            _ => Err(EvalError::WrongArgCount {
+                expected: 2,
+                got: args.len(),
+            }),
+        }
+    }
 
-                self.env = old_env;
-                match last {
-                    Some(obj) => obj,
-                    None => Object::Void,
-                }
+    /// Sorted copy of the list. The function receives two elements and
+    /// returns a negative number, zero or a positive number like `compare`
+    fn builtin_sort_by(&mut self, args: &[Object]) -> Result<Object, EvalError> {
+        let (list, func) = match args {
+            [Object::List(list), Object::Func(func)] => (list, func),
+            [Object::List(_), other] => {
+                return Err(EvalError::TypeMismatch {
+                    expected: "Func".into(),
+                    got: other.type_name().into(),
+                })
+            }
+            [other, _] => {
+                return Err(EvalError::TypeMismatch {
+                    expected: "List".into(),
+                    got: other.type_name().into(),
+                })
+            }
+            _ => {
+                return Err(EvalError::WrongArgCount {
+                    expected: 2,
+                    got: args.len(),
+                })
             }
+        };
+        let list = builtins::sort_stable(list.list.clone(), &mut |left, right| match self
+            .call_func(func, vec![left.clone(), right.clone()])
+        {
+            Object::Lit(Literal::Num(num)) => {
+                num.partial_cmp(&0.0).ok_or(EvalError::Incomparable {
+                    left: left.type_name().into(),
+                    right: right.type_name().into(),
+                })
+            }
+            Object::Err(err) => Err(err),
+            other => Err(EvalError::TypeMismatch {
+                expected: "Num".into(),
+                got: other.type_name().into(),
+            }),
+        })?;
+        Ok(self.alloc(Object::List(ListObj { list })))
+    }
+
+    fn assertion_failed(&self, msg: String) -> EvalError {
+        EvalError::AssertionFailed {
+            msg,
+            line: self.cur_line,
         }
     }
 
-    fn eval_args(&mut self, args: Vec<Expression>) -> Vec<Object> {
-        args.into_iter().map(|arg| self.eval_expr(arg)).collect()
+    /// Builtins that return a value evaluate to that value
+    fn builtin_ret_val(func: BuiltinFunc) -> Object {
+        match func.get_ret_val() {
+            Some(obj) => obj,
+            None => Object::BuiltinFunc(func),
+        }
+    }
+
+    fn eval_args(&mut self, args: Vec<Expression>) -> Result<Vec<Object>, EvalError> {
+        args.into_iter()
+            .map(|arg| match self.eval_expr(arg) {
+                Object::Err(err) => Err(err),
+                obj => Ok(obj),
+            })
+            .collect()
     }
 
+    /// Evaluates the block in a new scope.
+    /// Returns the value of the last statement
+    /// or `None` if the block is empty
     fn eval_block(&mut self, block: BlockStmt) -> Option<Object> {
-        let len = block.stmts.len();
+        self.env.push_scope();
+        let mut last = None;
+        let mut lines = block.lines.into_iter();
+        for stmt in block.stmts {
+            let obj = self.eval_stmt_at(stmt, lines.next());
+            let is_err = matches!(obj, Object::Err(_));
+            last = Some(obj);
+            if is_err {
+                break;
+            }
+        }
+        self.env.pop_scope();
+        last
+    }
 
-        for (i, stmt) in block.stmts.into_iter().enumerate() {
-            if i == len {
-                return Some(self.eval_stmt(stmt));
+    /// The if expression evaluates to the value of the branch that ran.
+    /// Conditions are checked in order and only until one of them is truthy.
+    /// If no branch ran, it evaluates to `none`
+    fn eval_if(&mut self, node: IfExpr) -> Object {
+        let alt_branch = node.branches.len();
+        for (i, branch) in node.branches.into_iter().enumerate() {
+            let cond = propagate_err!(self.eval_expr(*branch.cond));
+            if Self::is_truthy(&cond) {
+                self.hit_branch(i);
+                return self.eval_block(branch.block).unwrap_or(Object::None);
             }
-            self.eval_stmt(stmt);
         }
-        None
+        self.hit_branch(alt_branch);
+        match node.alt {
+            Some(alt) => self.eval_block(alt).unwrap_or(Object::None),
+            None => Object::None,
+        }
     }
 
     fn eval_ident(&mut self, node: Ident) -> Object {
-        match self.env.borrow().get(&node.0.clone()) {
+        match self.env.get(&node.0) {
             Some(obj) => obj.obj.clone(),
-            None => panic!("Could not find identifier: {}", node.0),
+            None => Object::Err(self.undefined(node.0)),
         }
     }
 
@@ -171,127 +1016,210 @@ impl Evaluator {
         match &node.op {
             PrefixOp::Pos => self.eval_expr(*node.val),
             PrefixOp::Neg => {
-                let val = self.eval_expr(*node.val);
-                Object::Lit(Literal::Num(match val {
-                    Object::Lit(lit) => match lit {
-                        Literal::Num(num) => -num,
-                        _ => panic!("The obj is not a number"),
+                let val = propagate_err!(self.eval_expr(*node.val));
+                Object::Lit(match val {
+                    Object::Lit(Literal::Num(num)) => Literal::Num(-num),
+                    Object::Lit(Literal::Decimal(dec)) => match dec.checked_neg() {
+                        Some(dec) => Literal::Decimal(dec),
+                        None => return Object::Err(EvalError::Overflow("Decimal".into())),
                     },
-                    _ => panic!("The obj does not evaluate to a literal"),
-                }))
+                    other => {
+                        return Object::Err(EvalError::TypeMismatch {
+                            expected: "Num or Decimal".into(),
+                            got: other.type_name().into(),
+                        })
+                    }
+                })
             }
             PrefixOp::Not => {
-                let val = self.eval_expr(*node.val);
-                Object::Lit(Literal::Bool(match val {
-                    Object::Lit(lit) => match lit {
-                        Literal::Bool(bool) => !bool,
-                        _ => panic!("The obj is not a boolean"),
-                    },
-                    _ => panic!("The obj does not evaluate to a literal"),
-                }))
+                let val = propagate_err!(self.eval_expr(*node.val));
+                match val {
+                    Object::Lit(Literal::Bool(bool)) => Object::Lit(Literal::Bool(!bool)),
+                    other => Object::Err(EvalError::TypeMismatch {
+                        expected: "Bool".into(),
+                        got: other.type_name().into(),
+                    }),
+                }
             }
         }
     }
 
+    /// Only the branch that is chosen gets evaluated
+    fn eval_ternary(&mut self, node: TernaryExpr) -> Object {
+        let cond = propagate_err!(self.eval_expr(*node.cond));
+        if Self::is_truthy(&cond) {
+            self.hit_branch(0);
+            self.eval_expr(*node.then)
+        } else {
+            self.hit_branch(1);
+            self.eval_expr(*node.alt)
+        }
+    }
+
     fn eval_infix(&mut self, node: InfixExpr) -> Object {
-        self.eval_infix_from_num(node.op, *node.left, *node.right)
+        let feature = match node.op {
+            InfixOp::As => Some("Casts"),
+            InfixOp::In => Some("In operators"),
+            InfixOp::Range => Some("Ranges"),
+            _ => None,
+        };
+        if let Some(feature) = feature {
+            return Object::Err(EvalError::Unsupported(feature.into()));
+        }
+        let left = propagate_err!(self.eval_expr(*node.left));
+        // The right side only gets evaluated if the left side is none
+        if node.op == InfixOp::NullCoalesce {
+            return match left {
+                Object::None => self.eval_expr(*node.right),
+                other => other,
+            };
+        }
+        let right = propagate_err!(self.eval_expr(*node.right));
+        if let InfixOp::GT | InfixOp::LT | InfixOp::GTEq | InfixOp::LTEq = node.op {
+            return Self::eval_comparison(node.op, &left, &right);
+        }
+        match (&left, &right) {
+            (Object::Lit(Literal::Decimal(_)), _) | (_, Object::Lit(Literal::Decimal(_))) => {
+                self.eval_infix_from_decimal(node.op, left, right)
+            }
+            _ => self.eval_infix_from_num(node.op, left, right),
+        }
     }
 
-    fn eval_infix_from_num(&mut self, op: InfixOp, left: Expression, right: Expression) -> Object {
+    /// `<`, `>`, `<=` and `>=`, see [`Object::compare`]
+    fn eval_comparison(op: InfixOp, left: &Object, right: &Object) -> Object {
+        let ordering = match left.compare(right) {
+            Ok(ordering) => ordering,
+            Err(err) => return Object::Err(err),
+        };
+        Object::Lit(Literal::Bool(match op {
+            InfixOp::GT => ordering.is_gt(),
+            InfixOp::LT => ordering.is_lt(),
+            InfixOp::GTEq => ordering.is_ge(),
+            _ => ordering.is_le(),
+        }))
+    }
+
+    fn eval_infix_from_num(&mut self, op: InfixOp, left: Object, right: Object) -> Object {
         match op {
-            InfixOp::Add => {
-                let (left, right) = self.eval_infix_to_num(left, right);
-                Object::Lit(Literal::Num(left + right))
-            }
-            InfixOp::Sub => {
-                let (left, right) = self.eval_infix_to_num(left, right);
-                Object::Lit(Literal::Num(left - right))
+            InfixOp::Add => match Self::infix_to_num(left, right) {
+                Ok((left, right)) => Object::Lit(Literal::Num(left + right)),
+                Err(err) => Object::Err(err),
+            },
+            InfixOp::Sub => match Self::infix_to_num(left, right) {
+                Ok((left, right)) => Object::Lit(Literal::Num(left - right)),
+                Err(err) => Object::Err(err),
+            },
+            InfixOp::Mul => match Self::infix_to_num(left, right) {
+                Ok((left, right)) => Object::Lit(Literal::Num(left * right)),
+                Err(err) => Object::Err(err),
+            },
+            InfixOp::Div => match Self::infix_to_num(left, right) {
+                Ok((left, right)) => Object::Lit(Literal::Num(left / right)),
+                Err(err) => Object::Err(err),
+            },
+            InfixOp::Eq => Object::Lit(Literal::Bool(left == right)),
+            InfixOp::NEq => Object::Lit(Literal::Bool(left != right)),
+            InfixOp::GT | InfixOp::LT | InfixOp::GTEq | InfixOp::LTEq => {
+                unreachable!("Comparisons are evaluated by eval_comparison")
             }
-            InfixOp::Mul => {
-                let (left, right) = self.eval_infix_to_num(left, right);
-                Object::Lit(Literal::Num(left * right))
+            InfixOp::As | InfixOp::In | InfixOp::Range => {
+                unreachable!("Unsupported operators are rejected by eval_infix")
             }
+            InfixOp::NullCoalesce => unreachable!(),
+        }
+    }
+
+    /// Infix operations where at least one side is a decimal.
+    /// Numbers on the other side get converted to decimals
+    fn eval_infix_from_decimal(&mut self, op: InfixOp, left: Object, right: Object) -> Object {
+        // Values of other types are never equal to decimals
+        match op {
+            InfixOp::Eq => return Object::Lit(Literal::Bool(left == right)),
+            InfixOp::NEq => return Object::Lit(Literal::Bool(left != right)),
+            _ => (),
+        }
+        let (left, right) = match (Self::conv_to_decimal(left), Self::conv_to_decimal(right)) {
+            (Ok(left), Ok(right)) => (left, right),
+            (Err(err), _) | (_, Err(err)) => return Object::Err(err),
+        };
+        let res = match op {
+            InfixOp::Add => left.checked_add(right),
+            InfixOp::Sub => left.checked_sub(right),
+            InfixOp::Mul => left.checked_mul(right),
             InfixOp::Div => {
-                let (left, right) = self.eval_infix_to_num(left, right);
-                Object::Lit(Literal::Num(left / right))
-            }
-            InfixOp::Eq => {
-                let (left, right) = self.eval_infix_to_comp(left, right);
-                Object::Lit(Literal::Bool(left == right))
-            }
-            InfixOp::NEq => {
-                let (left, right) = self.eval_infix_to_comp(left, right);
-                Object::Lit(Literal::Bool(left != right))
-            }
-            InfixOp::GT => {
-                let (left, right) = self.eval_infix_to_comp(left, right);
-                Object::Lit(Literal::Bool(left > right))
+                if right.is_zero() {
+                    return Object::Err(EvalError::DivisionByZero);
+                }
+                left.checked_div(right)
             }
-            InfixOp::LT => {
-                let (left, right) = self.eval_infix_to_comp(left, right);
-                Object::Lit(Literal::Bool(left < right))
+            InfixOp::Eq | InfixOp::NEq => unreachable!("Equality is checked before converting"),
+            InfixOp::GT | InfixOp::LT | InfixOp::GTEq | InfixOp::LTEq => {
+                unreachable!("Comparisons are evaluated by eval_comparison")
             }
-            InfixOp::GTEq => {
-                let (left, right) = self.eval_infix_to_comp(left, right);
-                Object::Lit(Literal::Bool(left >= right))
+            InfixOp::As | InfixOp::In | InfixOp::Range => {
+                unreachable!("Unsupported operators are rejected by eval_infix")
             }
-            InfixOp::LTEq => {
-                let (left, right) = self.eval_infix_to_comp(left, right);
-                Object::Lit(Literal::Bool(left <= right))
+            InfixOp::NullCoalesce => unreachable!(),
+        };
+        match res {
+            Some(dec) => Object::Lit(Literal::Decimal(dec)),
+            None => Object::Err(EvalError::Overflow("Decimal".into())),
+        }
+    }
+
+    fn infix_to_num(left: Object, right: Object) -> Result<(f64, f64), EvalError> {
+        match (left, right) {
+            (Object::Lit(Literal::Num(left)), Object::Lit(Literal::Num(right))) => {
+                Ok((left, right))
             }
-            InfixOp::As => todo!(),
-            InfixOp::In => todo!(),
-            InfixOp::Range => todo!(),
-            InfixOp::Assign => todo!(),
+            (Object::Lit(Literal::Num(_)), other) | (other, _) => Err(EvalError::TypeMismatch {
+                expected: "Num".into(),
+                got: other.type_name().into(),
+            }),
         }
     }
 
-    fn eval_infix_to_num(&mut self, left: Expression, right: Expression) -> (f64, f64) {
-        let left = self.eval_expr(left);
-        let right = self.eval_expr(right);
-        (
-            match Self::conv_to_num(left) {
-                Some(val) => val,
-                None => panic!("Left of the infix expression cannot is not a number"),
-            },
-            match Self::conv_to_num(right) {
-                Some(val) => val,
-                None => panic!("Right of the infix expression cannot is not a number"),
-            },
-        )
+    /// `false` and `none` are falsy, every other value is truthy
+    fn is_truthy(obj: &Object) -> bool {
+        !matches!(obj, Object::Lit(Literal::Bool(false)) | Object::None)
     }
 
-    fn eval_infix_to_comp(
-        &mut self,
-        left: Expression,
-        right: Expression,
-    ) -> (Comparable, Comparable) {
-        let left = self.eval_expr(left);
-        let right = self.eval_expr(right);
-        (
-            match left {
-                Object::Lit(lit) => Comparable::Lit(lit),
-                Object::None => Comparable::None,
-                _ => panic!(
-                    "Cannot compare left: {:#?} since it is not valid for comparison",
-                    &left
-                ),
-            },
-            match right {
-                Object::Lit(lit) => Comparable::Lit(lit),
-                Object::None => Comparable::None,
-                _ => panic!(
-                    "Cannot compare right: {:#?} since it is not valid for comparison",
-                    &right
-                ),
-            },
-        )
+    fn conv_to_index(obj: Object) -> Result<i64, EvalError> {
+        match obj {
+            Object::Lit(Literal::Num(num)) if num.fract() == 0.0 => Ok(num as i64),
+            other => Err(EvalError::InvalidIndex(other.to_string())),
+        }
     }
 
-    fn conv_to_num(obj: Object) -> Option<f64> {
+    /// Converts an index into a position in a sequence of length `len`.
+    /// Negative indices count from the end, so `-1` is the last element.
+    /// `allow_end` permits `len` itself which is needed for the end of slices
+    fn resolve_index(index: i64, len: usize, allow_end: bool) -> Result<usize, EvalError> {
+        let pos = if index < 0 { len as i64 + index } else { index };
+        let max = if allow_end {
+            len as i64
+        } else {
+            len as i64 - 1
+        };
+        if pos < 0 || pos > max {
+            return Err(EvalError::IndexOutOfBounds { index, len });
+        }
+        Ok(pos as usize)
+    }
+
+    /// Numbers that have no decimal representation, like
+    /// infinity or `1e40`, are an overflow
+    fn conv_to_decimal(obj: Object) -> Result<Decimal, EvalError> {
         match obj {
-            Object::Lit(Literal::Num(num)) => Some(num),
-            _ => None,
+            Object::Lit(Literal::Decimal(dec)) => Ok(dec),
+            Object::Lit(Literal::Num(num)) => {
+                Decimal::from_f64(num).ok_or(EvalError::Overflow("Decimal".into()))
+            }
+            other => Err(EvalError::TypeMismatch {
+                expected: "Decimal".into(),
+                got: other.type_name().into(),
+            }),
         }
     }
 
@@ -302,3 +1230,10 @@ impl Evaluator {
         }
     }
 }
+
+/// Step of the path from a variable to the element or field that gets assigned
+enum Place<T = Object> {
+    /// Index of a list or key of a hash
+    Index(T),
+    Field(String),
+}
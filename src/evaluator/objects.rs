@@ -1,19 +1,28 @@
-use std::fmt::Display;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::Display,
+    mem,
+    sync::{
+        mpsc::{Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
 
 use crate::{
-    evaluator::builtins::BuiltinFunc,
-    parser::ast::{BlockStmt, Ident, OptionallyTypedIdent, Literal},
+    evaluator::{builtins::BuiltinFunc, errors::EvalError},
+    parser::ast::{BlockStmt, Ident, Literal, OptionallyTypedIdent},
 };
 
-use crate::util;
+use crate::util::{self, decimal::Decimal};
 
 #[derive(Debug, Clone)]
 pub enum Object {
     Lit(Literal),
     None,
     Void,
-    // TODO: Implement error system
-    Err,
+    Err(EvalError),
     // TOOD: Implement multi file shenanigans
     Use,
     // Rc<Object> is the return value
@@ -25,7 +34,10 @@ pub enum Object {
     // TODO: Implement these
     Range,
     Type,
-    List,
+    List(ListObj),
+    Hash(HashObj),
+    Thread(ThreadObj),
+    Channel(ChannelObj),
 }
 
 #[derive(Debug, Clone)]
@@ -34,38 +46,304 @@ pub struct FuncObj {
     pub block: BlockStmt,
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
-pub enum Comparable {
-    Lit(Literal),
-    None,
+/// Function that runs on another thread.
+/// The handle is taken out once the thread is joined
+#[derive(Debug, Clone)]
+pub struct ThreadObj {
+    pub handle: Arc<Mutex<Option<JoinHandle<Object>>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelObj {
+    pub sender: Sender<Object>,
+    pub receiver: Arc<Mutex<Receiver<Object>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListObj {
+    pub list: Vec<Object>,
 }
 
+/// View of a string value that implements the string semantics of nexus.
+///
+/// Strings are sequences of characters (unicode scalar values), not bytes.
+/// Lengths, indices and iteration therefore never split up an utf-8 sequence,
+/// so `len("héllo")` is `5`. Everything that deals with positions in strings
+/// should go through this type
+#[derive(Debug, Clone, Copy)]
+pub struct StrObj<'a>(pub &'a str);
+
+impl<'a> StrObj<'a> {
+    /// Amount of characters in the string
+    pub fn len(&self) -> usize {
+        self.0.chars().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Character at the position as a string of length one
+    pub fn get(&self, index: usize) -> Option<String> {
+        self.0.chars().nth(index).map(|ch| ch.to_string())
+    }
+
+    /// Characters from `start` up to (excluding) `end`
+    pub fn slice(&self, start: usize, end: usize) -> String {
+        self.0.chars().skip(start).take(end - start).collect()
+    }
+
+    /// Iterates over the characters as string objects
+    pub fn iter(&self) -> impl Iterator<Item = Object> + 'a {
+        self.0
+            .chars()
+            .map(|ch| Object::Lit(Literal::Str(ch.to_string())))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HashObj {
+    pub map: HashMap<HashKey, Object>,
+}
+
+/// Objects that can be used as keys of a hash.
+/// Only strings, whole numbers that fit into an `i64` and booleans are hashable
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HashKey {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl TryFrom<&Object> for HashKey {
+    type Error = EvalError;
+
+    fn try_from(obj: &Object) -> Result<Self, Self::Error> {
+        match obj {
+            Object::Lit(Literal::Str(str)) => Ok(HashKey::Str(str.clone())),
+            // Casting would saturate bigger numbers, so different numbers would be the same key
+            Object::Lit(Literal::Num(num))
+                if num.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(num) =>
+            {
+                Ok(HashKey::Int(*num as i64))
+            }
+            Object::Lit(Literal::Bool(bool)) => Ok(HashKey::Bool(*bool)),
+            other => Err(EvalError::UnhashableKey(other.type_name().into())),
+        }
+    }
+}
+
+impl From<HashKey> for Object {
+    fn from(key: HashKey) -> Self {
+        Object::Lit(match key {
+            HashKey::Str(str) => Literal::Str(str),
+            HashKey::Int(int) => Literal::Num(int as f64),
+            HashKey::Bool(bool) => Literal::Bool(bool),
+        })
+    }
+}
+
+impl Display for HashKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashKey::Str(str) => write!(f, "{}", str),
+            HashKey::Int(int) => write!(f, "{}", int),
+            HashKey::Bool(bool) => write!(f, "{}", bool),
+        }
+    }
+}
+
+/// User facing representation that is used by `print`.
+/// Strings and decimals are shown as they are,
+/// unless they are inside of a list or hash
 impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Object::Lit(lit) => lit.to_string(),
-                Object::None => "none".into(),
-                Object::Err => todo!(),
-                Object::Use => todo!(),
-                Object::Ret(_) => todo!(),
-                Object::Br(_) => todo!(),
-                Object::Func(func) => format!(
-                    "func({}) {{\n{}\n}}",
-                    util::typed_vec_to_string(&func.args),
-                    util::block_to_string(&func.block)
-                ),
-                Object::BuiltinFunc(func) => match func.get_ret_val() {
-                    Some(func) => func.to_string(),
-                    None => todo!(),
-                },
-                Object::Range => todo!(),
-                Object::Type => todo!(),
-                Object::List => todo!(),
-                Object::Void => "()".into()
+        match self {
+            Object::Lit(lit) => write!(f, "{}", lit),
+            other => f.write_str(&other.inspect()),
+        }
+    }
+}
+
+impl Object {
+    /// Representation that is used by the repl.
+    /// Unlike `Display` it quotes strings and marks decimals,
+    /// so `"1"`, `1` and `1d` can be told apart
+    pub fn inspect(&self) -> String {
+        match self {
+            Object::Lit(Literal::Str(str)) => format!("\"{}\"", str),
+            Object::Lit(Literal::Decimal(dec)) => format!("{}d", dec),
+            Object::Lit(lit) => lit.to_string(),
+            Object::None => "none".into(),
+            Object::Void => "()".into(),
+            Object::Err(err) => format!("Error: {}", err),
+            Object::Use => "<use>".into(),
+            Object::Ret(val) => val.inspect(),
+            Object::Br(label) => format!("<break {}>", label),
+            Object::Func(func) => format!("<func({})>", util::typed_vec_to_string(&func.args)),
+            Object::BuiltinFunc(func) => match func.get_ret_val() {
+                Some(val) => val.inspect(),
+                None => "()".into(),
+            },
+            Object::Range => "<range>".into(),
+            Object::Type => "<type>".into(),
+            Object::List(list) => format!(
+                "[{}]",
+                list.list
+                    .iter()
+                    .map(Object::inspect)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Object::Hash(hash) => {
+                let mut pairs: Vec<_> = hash.map.iter().collect();
+                pairs.sort_by_key(|(key, _)| *key);
+                format!(
+                    "{{{}}}",
+                    pairs
+                        .iter()
+                        .map(|(key, val)| format!(
+                            "{}: {}",
+                            Object::from((*key).clone()).inspect(),
+                            val.inspect()
+                        ))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            }
+            Object::Thread(_) => "<thread>".into(),
+            Object::Channel(_) => "<channel>".into(),
+        }
+    }
+
+    /// Name of the type of the object
+    /// that is used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Lit(Literal::Str(_)) => "Str",
+            Object::Lit(Literal::Num(_)) => "Num",
+            Object::Lit(Literal::Decimal(_)) => "Decimal",
+            Object::Lit(Literal::Bool(_)) => "Bool",
+            Object::None => "None",
+            Object::Void => "Void",
+            Object::Err(_) => "Error",
+            Object::Use => "Use",
+            Object::Ret(_) => "Return",
+            Object::Br(_) => "Break",
+            Object::Func(_) | Object::BuiltinFunc(_) => "Func",
+            Object::Range => "Range",
+            Object::Type => "Type",
+            Object::List(_) => "List",
+            Object::Hash(_) => "Hash",
+            Object::Thread(_) => "Thread",
+            Object::Channel(_) => "Channel",
+        }
+    }
+
+    /// Rough estimate of the bytes used by the object,
+    /// including the contents of lists, hashes and strings
+    pub fn approx_size(&self) -> usize {
+        let heap = match self {
+            Object::Lit(Literal::Str(str)) => str.len(),
+            Object::List(list) => list.list.iter().map(Object::approx_size).sum(),
+            Object::Hash(hash) => hash
+                .map
+                .iter()
+                .map(|(key, val)| {
+                    let key_heap = match key {
+                        HashKey::Str(str) => str.len(),
+                        _ => 0,
+                    };
+                    mem::size_of::<HashKey>() + key_heap + val.approx_size()
+                })
+                .sum(),
+            _ => 0,
+        };
+        mem::size_of::<Object>() + heap
+    }
+}
+
+/// Equality semantics used by `==` and `!=`:
+///
+/// - Literals are equal if their values are equal.
+///   Numbers and decimals are compared by their numeric value
+/// - Lists are equal if they have the same length
+///   and all of their elements are equal (recursively)
+/// - `none` and `()` are only equal to themselves
+/// - Errors are equal if they are the same kind of error
+/// - Hashes are equal if they have the same keys
+///   and the values of each key are equal
+/// - Functions are never equal to anything,
+///   not even to themselves
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Object::Lit(left), Object::Lit(right)) => lit_eq(left, right),
+            (Object::None, Object::None) => true,
+            (Object::Void, Object::Void) => true,
+            (Object::Err(left), Object::Err(right)) => left == right,
+            (Object::List(left), Object::List(right)) => {
+                left.list.len() == right.list.len()
+                    && left.list.iter().zip(&right.list).all(|(l, r)| l == r)
             }
-        )
+            (Object::Hash(left), Object::Hash(right)) => {
+                left.map.len() == right.map.len()
+                    && left
+                        .map
+                        .iter()
+                        .all(|(key, val)| right.map.get(key).is_some_and(|other| val == other))
+            }
+            _ => false,
+        }
+    }
+}
+
+fn lit_eq(left: &Literal, right: &Literal) -> bool {
+    match (left, right) {
+        (Literal::Num(num), Literal::Decimal(dec)) | (Literal::Decimal(dec), Literal::Num(num)) => {
+            Decimal::from_f64(*num).is_some_and(|num| num == *dec)
+        }
+        _ => left == right,
+    }
+}
+
+impl Object {
+    /// Ordering that is used by `<`, `>`, `<=`, `>=` and `compare`.
+    ///
+    /// Numbers and decimals are ordered by their value, strings by their
+    /// characters, `false` comes before `true`. Lists are ordered by their
+    /// first differing element, a list that is the start of another one
+    /// comes first. Everything else, including values of different types
+    /// and `NaN`, cannot be compared
+    pub fn compare(&self, other: &Object) -> Result<Ordering, EvalError> {
+        let ordering = match (self, other) {
+            (Object::Lit(left), Object::Lit(right)) => lit_cmp(left, right),
+            (Object::List(left), Object::List(right)) => {
+                for (left, right) in left.list.iter().zip(&right.list) {
+                    match left.compare(right)? {
+                        Ordering::Equal => (),
+                        ordering => return Ok(ordering),
+                    }
+                }
+                Some(left.list.len().cmp(&right.list.len()))
+            }
+            _ => None,
+        };
+        ordering.ok_or_else(|| EvalError::Incomparable {
+            left: self.type_name().into(),
+            right: other.type_name().into(),
+        })
+    }
+}
+
+fn lit_cmp(left: &Literal, right: &Literal) -> Option<Ordering> {
+    match (left, right) {
+        (Literal::Num(left), Literal::Num(right)) => left.partial_cmp(right),
+        (Literal::Decimal(left), Literal::Decimal(right)) => left.partial_cmp(right),
+        (Literal::Num(num), Literal::Decimal(dec)) => Decimal::from_f64(*num)?.partial_cmp(dec),
+        (Literal::Decimal(dec), Literal::Num(num)) => dec.partial_cmp(&Decimal::from_f64(*num)?),
+        (Literal::Str(left), Literal::Str(right)) => Some(left.cmp(right)),
+        (Literal::Bool(left), Literal::Bool(right)) => Some(left.cmp(right)),
+        _ => None,
     }
 }
@@ -0,0 +1,37 @@
+use std::fmt::Debug;
+
+use crate::parser::ast::Statement;
+
+use super::{env::Environment, errors::EvalError, objects::Object};
+
+/// Callbacks that the evaluator invokes while it runs a program,
+/// e.g. for loggers, profilers or debuggers.
+///
+/// All methods do nothing by default, so observers
+/// only need to implement the events they are interested in.
+/// Install an observer with `Evaluator::set_observer`
+pub trait EvalObserver: Send + Sync {
+    /// Called before a statement is evaluated.
+    /// The line is `None` if the statement was not created by the parser
+    fn on_statement_enter(&mut self, _stmt: &Statement, _line: Option<usize>, _env: &Environment) {}
+
+    /// Called after a statement was evaluated with its result
+    fn on_statement_exit(&mut self, _result: &Object) {}
+
+    /// Called before a function (builtin or user defined)
+    /// is called with the already evaluated arguments
+    fn on_call(&mut self, _name: &str, _args: &[Object]) {}
+
+    /// Called after a function returned
+    fn on_return(&mut self, _name: &str, _result: &Object) {}
+
+    /// Called once for every error, in the statement where it occurred.
+    /// The statements that the error propagates through are not reported again
+    fn on_error(&mut self, _err: &EvalError) {}
+}
+
+impl Debug for dyn EvalObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EvalObserver")
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// Records how often functions and lines run and how long they take.
+/// Enabled with `EvalConfig::profile`.
+///
+/// Times are inclusive, so the time of a function contains the time of
+/// the functions it calls. Recursive calls are only counted once
+/// for the time, because they overlap with the outermost call
+#[derive(Debug, Default)]
+pub struct Profiler {
+    functions: Timings<String>,
+    lines: Timings<usize>,
+}
+
+impl Profiler {
+    pub(super) fn enter_function(&mut self, name: &str) -> Instant {
+        self.functions.enter(name.to_string())
+    }
+
+    pub(super) fn exit_function(&mut self, name: &str, start: Instant) {
+        self.functions.exit(name.to_string(), start)
+    }
+
+    pub(super) fn enter_line(&mut self, line: usize) -> Instant {
+        self.lines.enter(line)
+    }
+
+    pub(super) fn exit_line(&mut self, line: usize, start: Instant) {
+        self.lines.exit(line, start)
+    }
+
+    /// Functions and lines sorted by their time, the slowest first
+    pub fn report(&self) -> ProfileReport {
+        ProfileReport {
+            functions: self.functions.entries(),
+            lines: self.lines.entries(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Timings<K> {
+    entries: HashMap<K, ProfileEntry<K>>,
+    /// How many calls of each key are currently running
+    active: HashMap<K, usize>,
+}
+
+impl<K> Default for Timings<K> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            active: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> Timings<K> {
+    fn enter(&mut self, key: K) -> Instant {
+        *self.active.entry(key.clone()).or_default() += 1;
+        self.entries
+            .entry(key.clone())
+            .or_insert_with(|| ProfileEntry {
+                key,
+                count: 0,
+                time: Duration::ZERO,
+            })
+            .count += 1;
+        Instant::now()
+    }
+
+    fn exit(&mut self, key: K, start: Instant) {
+        let elapsed = start.elapsed();
+        let active = self.active.entry(key.clone()).or_default();
+        *active = active.saturating_sub(1);
+        if *active == 0 {
+            if let Some(entry) = self.entries.get_mut(&key) {
+                entry.time += elapsed;
+            }
+        }
+    }
+
+    fn entries(&self) -> Vec<ProfileEntry<K>> {
+        let mut entries: Vec<_> = self.entries.values().cloned().collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.time));
+        entries
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileEntry<K> {
+    /// Name of the function or line
+    pub key: K,
+    /// How often the function was called or the line ran
+    pub count: usize,
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileReport {
+    pub functions: Vec<ProfileEntry<String>>,
+    pub lines: Vec<ProfileEntry<usize>>,
+}
+
+/// Prints the report as two tables
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<24} {:>8} {:>12}", "function", "calls", "time")?;
+        for entry in &self.functions {
+            writeln!(
+                f,
+                "{:<24} {:>8} {:>12?}",
+                entry.key, entry.count, entry.time
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{:<24} {:>8} {:>12}", "line", "hits", "time")?;
+        for entry in &self.lines {
+            writeln!(
+                f,
+                "{:<24} {:>8} {:>12?}",
+                entry.key, entry.count, entry.time
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,1175 @@
 #[cfg(test)]
 mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use crate::codes::ErrorCode;
     use crate::lexer::Lexer;
-    use crate::parser::Parser;
+    use crate::parser::ast::{BlockStmt, Literal, Statement};
+    use crate::parser::{parse_source, Parser};
 
-    use crate::evaluator::Evaluator;
+    use crate::evaluator::builtins::BuiltinProfile;
+    use crate::evaluator::env::{EnvObj, Environment};
+    use crate::evaluator::errors::{EvalError, EvalWarning, SourceError};
+    use crate::evaluator::io::BufferedIo;
+    use crate::evaluator::objects::{FuncObj, ListObj, Object, StrObj};
+    use crate::evaluator::observer::EvalObserver;
+    use crate::evaluator::{EvalConfig, Evaluator, DEFAULT_MAX_CALL_DEPTH};
+    use crate::util::{self, decimal::Decimal};
 
     #[test]
     fn test_evaluator() {
         let mut lexer = Lexer::new(&"tests/evaluator/test.nx".into()).expect("Failed to find file");
         let mut parser = Parser::new(&mut lexer);
         let mut evaluator = Evaluator::new();
-        evaluator.eval_stmt(parser.parse_stmt().expect("Encountered eol"));
-        parser.next_token();
-        evaluator.eval_stmt(parser.parse_stmt().expect("Encountered eol"));
+        evaluator.eval_stmt(parser.parse_stmt().unwrap().expect("Encountered eof"));
+        parser.next_token().unwrap();
+        evaluator.eval_stmt(parser.parse_stmt().unwrap().expect("Encountered eof"));
+    }
+
+    /// Test for checking if decimals do exact base-10 arithmetic
+    /// and are never equal to values that are not numbers
+    #[test]
+    fn test_decimal() {
+        assert_all_true("decimal");
+
+        let third = Decimal::new(1, 0).checked_div(Decimal::new(3, 0)).unwrap();
+        assert_eq!("0.3333333333333333333333333333", third.to_string());
+        assert_eq!(Decimal::new(150, 2), Decimal::new(15, 1));
+        assert_eq!(Some(Decimal::new(1, 1)), Decimal::from_f64(0.1));
+
+        assert_eq!(
+            vec![
+                Object::Err(EvalError::DivisionByZero),
+                Object::Err(EvalError::Overflow("Decimal".into())),
+                Object::Err(EvalError::Overflow("Decimal".into())),
+                Object::Err(EvalError::Overflow("Decimal".into())),
+                Object::Err(EvalError::TypeMismatch {
+                    expected: "Decimal".into(),
+                    got: "Str".into(),
+                }),
+            ],
+            eval_file("decimal_errors")
+        );
+    }
+
+    /// Test for checking if `==` compares lists
+    /// element by element and never considers
+    /// two functions equal
+    #[test]
+    fn test_equality() {
+        assert_all_true("equality");
+
+        let func = Object::Func(FuncObj {
+            args: Vec::new(),
+            block: BlockStmt::new(Vec::new()),
+        });
+        assert_ne!(func, func.clone());
+    }
+
+    /// Test for checking if hashes can be indexed with
+    /// hashable keys and if unhashable keys produce an error
+    #[test]
+    fn test_hash() {
+        assert_all_true("hash");
+
+        let errs = eval_file("unhashable");
+        assert_eq!(5, errs.len());
+        for (err, _type) in errs.iter().zip(["List", "Func", "Num", "Num", "Num"]) {
+            assert_eq!(&Object::Err(EvalError::UnhashableKey(_type.into())), err);
+        }
+    }
+
+    /// Test for checking if lists and strings can be sliced
+    /// and if invalid bounds produce an error
+    #[test]
+    fn test_slice() {
+        assert_all_true("slice");
+
+        let errs = eval_file("slice_errors");
+        assert_eq!(
+            Object::Err(EvalError::IndexOutOfBounds { index: 3, len: 2 }),
+            errs[0]
+        );
+        assert_eq!(
+            Object::Err(EvalError::InvalidSlice { start: 2, end: 1 }),
+            errs[1]
+        );
+    }
+
+    /// Test for checking if negative indices
+    /// address elements from the end
+    #[test]
+    fn test_negative_index() {
+        assert_all_true("negative_index");
+
+        let errs = eval_file("index_errors");
+        assert_eq!(
+            Object::Err(EvalError::IndexOutOfBounds { index: -4, len: 3 }),
+            errs[0]
+        );
+        assert_eq!(
+            Object::Err(EvalError::IndexOutOfBounds { index: 3, len: 3 }),
+            errs[1]
+        );
+    }
+
+    /// Test for checking if variables, elements and fields can be assigned,
+    /// including variables of outer scopes, and if constants cannot
+    #[test]
+    fn test_assign() {
+        assert_all_true("assign");
+
+        let objs = eval_file("assign_errors");
+        assert_eq!(
+            vec![
+                Object::Err(EvalError::ConstAssignment("limit".into())),
+                Object::Err(EvalError::UndefinedVariable {
+                    name: "missing".into(),
+                    suggestion: None,
+                }),
+                Object::Err(EvalError::IndexOutOfBounds { index: 2, len: 2 }),
+                Object::Err(EvalError::IndexOutOfBounds { index: -3, len: 2 }),
+                Object::Err(EvalError::TypeMismatch {
+                    expected: "List or Hash".into(),
+                    got: "Str".into(),
+                }),
+                Object::Err(EvalError::UnknownField {
+                    _type: "Str".into(),
+                    field: "first".into(),
+                }),
+                Object::Lit(Literal::Num(1.0)),
+                Object::List(ListObj {
+                    list: vec![
+                        Object::Lit(Literal::Num(1.0)),
+                        Object::Lit(Literal::Num(2.0))
+                    ],
+                }),
+            ],
+            objs
+        );
+    }
+
+    /// Test for checking if strings and lists are ordered, if `compare`
+    /// agrees with the operators and if other values cannot be ordered
+    #[test]
+    fn test_ordering() {
+        assert_all_true("ordering");
+
+        let eval = |source: &str| Evaluator::new().eval_expression_source(source);
+        let incomparable = |left: &str, right: &str| {
+            Err(SourceError::Eval(EvalError::Incomparable {
+                left: left.into(),
+                right: right.into(),
+            }))
+        };
+        assert_eq!(incomparable("Str", "Num"), eval("\"1\" < 2"));
+        assert_eq!(incomparable("None", "Num"), eval("none > 0"));
+        assert_eq!(incomparable("Num", "Str"), eval("[1, 2] < [1, \"2\"]"));
+        assert_eq!(incomparable("Hash", "Hash"), eval("compare({}, {})"));
+    }
+
+    /// Test for checking if sorting keeps the order of equal elements,
+    /// calls the comparison of the program and fails on incomparable elements
+    #[test]
+    fn test_sort() {
+        assert_all_true("sort");
+
+        let eval = |source: &str| Evaluator::new().eval_expression_source(source).unwrap_err();
+        assert_eq!(
+            SourceError::Eval(EvalError::Incomparable {
+                left: "Str".into(),
+                right: "Num".into(),
+            }),
+            eval("sort([1, \"a\"])")
+        );
+        assert_eq!(
+            SourceError::Eval(EvalError::TypeMismatch {
+                expected: "Num".into(),
+                got: "Bool".into(),
+            }),
+            eval("sort_by([1, 2], func(a, b) { a < b })")
+        );
+        assert_eq!(
+            SourceError::Eval(EvalError::WrongArgCount {
+                expected: 1,
+                got: 2,
+            }),
+            eval("sort_by([1, 2], func(a) { a })")
+        );
+        assert_eq!(
+            SourceError::Eval(EvalError::TypeMismatch {
+                expected: "List".into(),
+                got: "Str".into(),
+            }),
+            eval("sort(\"cba\")")
+        );
+    }
+
+    /// Test for checking if unknown names suggest a declared variable,
+    /// builtin or function of the host with a similar name
+    #[test]
+    fn test_suggestions() {
+        let mut evaluator = Evaluator::new();
+        evaluator.register_builtin("fetch_all", |_| Ok(Object::None));
+        let errs: Vec<String> = eval_file_with("suggestions", &mut evaluator)
+            .into_iter()
+            .map(|obj| obj.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "Error: prnt is not declared, did you mean 'print'?",
+                "Error: totl is not declared, did you mean 'total'?",
+                "Error: fetchall is not declared, did you mean 'fetch_all'?",
+                "Error: sotr is not declared, did you mean 'sort'?",
+                "Error: counter is not declared, did you mean 'count'?",
+                "Error: xyz is not declared",
+            ],
+            errs
+        );
+
+        let mut sandboxed = Evaluator::with_config(EvalConfig {
+            builtins: BuiltinProfile::Pure,
+            ..Default::default()
+        });
+        assert_eq!(
+            vec![Object::Err(EvalError::UndefinedVariable {
+                name: "prnt".into(),
+                suggestion: None,
+            })],
+            eval_file_with("suggestions", &mut sandboxed)[..1]
+        );
+
+        assert_eq!(0, util::edit_distance("len", "len"));
+        assert_eq!(1, util::edit_distance("pritn", "print"));
+        assert_eq!(3, util::edit_distance("", "abc"));
+        assert_eq!(
+            Some("bar"),
+            util::closest_match("baz", ["bar", "bat", "foo"])
+        );
+    }
+
+    /// Test for checking if strings are indexed,
+    /// sliced and measured by characters instead of bytes
+    #[test]
+    fn test_unicode() {
+        assert_all_true("unicode");
+
+        let chars: Vec<Object> = StrObj("añb").iter().collect();
+        assert_eq!(
+            vec![
+                Object::Lit(Literal::Str("a".into())),
+                Object::Lit(Literal::Str("ñ".into())),
+                Object::Lit(Literal::Str("b".into())),
+            ],
+            chars
+        );
+    }
+
+    /// Test for checking if if expressions evaluate
+    /// to the value of the branch that ran
+    #[test]
+    fn test_if() {
+        assert_all_true("if");
+
+        let mut lexer = Lexer::new(&"tests/evaluator/if.nx".into()).expect("Failed to find file");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        let obj = Evaluator::new().eval_program(program);
+        assert_eq!(Object::Lit(Literal::Bool(true)), obj);
+    }
+
+    /// Test for checking if long `else if` chains
+    /// run exactly one branch and stop checking
+    /// conditions after the first match
+    #[test]
+    fn test_else_if() {
+        assert_all_true("else_if");
+    }
+
+    /// Test for checking if blocks evaluate to their last value,
+    /// both as expressions and as function bodies
+    #[test]
+    fn test_block() {
+        assert_all_true("block");
+    }
+
+    /// Test for checking the precedence of the ternary operator
+    /// and that the branch that is not taken is never evaluated
+    #[test]
+    fn test_ternary() {
+        assert_all_true("ternary");
+    }
+
+    /// Test for checking if `??`, `?.` and `?[`
+    /// turn a none receiver into none instead of an error
+    #[test]
+    fn test_null_safety() {
+        assert_all_true("null_safety");
+
+        let errs = eval_file("null_errors");
+        assert_eq!(
+            vec![
+                Object::Err(EvalError::UnknownField {
+                    _type: "None".into(),
+                    field: "field".into()
+                }),
+                Object::Err(EvalError::NotIndexable("None".into())),
+                Object::Err(EvalError::UnknownField {
+                    _type: "Num".into(),
+                    field: "field".into()
+                }),
+            ],
+            errs
+        );
+    }
+
+    /// Test for checking if expressions can span multiple lines
+    /// inside of brackets and after trailing operators
+    #[test]
+    fn test_multiline() {
+        assert_all_true("multiline");
+    }
+
+    /// Test for checking if inner scopes can shadow outer
+    /// variables and when redeclarations are rejected
+    #[test]
+    fn test_scope() {
+        assert_all_true("scope");
+
+        let mut evaluator = Evaluator::new();
+        let objs = eval_file_with("scope", &mut evaluator);
+        assert_eq!(4, objs.len());
+        assert_eq!(
+            &[
+                EvalWarning::Shadowing("x".into()),
+                EvalWarning::Shadowing("x".into())
+            ],
+            evaluator.warnings()
+        );
+
+        let redeclared = Object::Err(EvalError::Redeclaration("c".into()));
+        assert_eq!(
+            vec![Object::Lit(Literal::Num(2.0)), redeclared.clone()],
+            eval_file("redeclaration")
+        );
+
+        let mut strict = Evaluator::with_config(EvalConfig {
+            strict: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            vec![
+                Object::Err(EvalError::Redeclaration("a".into())),
+                redeclared
+            ],
+            eval_file_with("redeclaration", &mut strict)
+        );
+        assert!(matches!(
+            strict.env.get(&"limit".into()),
+            Some(EnvObj { obj: Object::Lit(Literal::Num(num)), .. }) if *num == 10.0
+        ));
+    }
+
+    /// Test for checking if the host can read the globals
+    /// of a program and call its functions afterwards
+    #[test]
+    fn test_globals() {
+        let mut lexer =
+            Lexer::new(&"tests/evaluator/globals.nx".into()).expect("Failed to find file");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        let mut evaluator = Evaluator::new();
+        evaluator.eval_program(program);
+
+        let mut names: Vec<String> = evaluator.globals().map(|(name, _)| name).collect();
+        names.sort();
+        assert_eq!(vec!["count", "main"], names);
+
+        let main = evaluator.get_function("main").expect("Failed to find main");
+        let args = vec![
+            Object::Lit(Literal::Num(1.0)),
+            Object::Lit(Literal::Num(2.0)),
+        ];
+        assert_eq!(
+            Object::Lit(Literal::Num(9.0)),
+            evaluator.call_function(&main, args)
+        );
+        assert_eq!(
+            Object::Err(EvalError::WrongArgCount {
+                expected: 2,
+                got: 0
+            }),
+            evaluator.call_function(&main, Vec::new())
+        );
+        assert!(evaluator.get_function("count").is_none());
+        assert!(evaluator.globals().all(|(name, _)| name != "sum"));
+    }
+
+    /// Test for checking if endless recursion is stopped
+    /// once the fuel runs out and if the fuel can be refilled
+    #[test]
+    fn test_fuel() {
+        let mut lexer = Lexer::new(&"tests/evaluator/fuel.nx".into()).expect("Failed to find file");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            fuel: Some(200),
+            ..Default::default()
+        });
+        assert_eq!(
+            Object::Err(EvalError::OutOfFuel),
+            evaluator.eval_program(program)
+        );
+        assert_eq!(Some(0), evaluator.remaining_fuel());
+
+        evaluator.refuel();
+        let main = evaluator.get_function("main").expect("Failed to find main");
+        assert_eq!(
+            Object::Lit(Literal::Num(3.0)),
+            evaluator.call_function(&main, Vec::new())
+        );
+        assert!(evaluator.remaining_fuel().is_some_and(|fuel| fuel < 200));
+    }
+
+    /// Test for checking if endless recursion is aborted before
+    /// the native stack overflows and if the depth is reset afterwards
+    #[test]
+    fn test_max_call_depth() {
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            fuel: Some(1_000_000),
+            ..Default::default()
+        });
+        assert_eq!(
+            Object::Err(EvalError::StackOverflow {
+                limit: DEFAULT_MAX_CALL_DEPTH
+            }),
+            evaluator.eval_program(parse_source("var f = func(n) { f(n) }\nf(1)").unwrap())
+        );
+
+        evaluator.config.max_call_depth = 3;
+        let program = parse_source("var g = func(n) { n == 0 ? 0 : g(n - 1) }").unwrap();
+        evaluator.eval_program(program);
+        let call = |evaluator: &mut Evaluator, source: &str| {
+            evaluator.eval_program(parse_source(source).unwrap())
+        };
+        assert_eq!(Object::Lit(Literal::Num(0.0)), call(&mut evaluator, "g(2)"));
+        assert_eq!(
+            Object::Err(EvalError::StackOverflow { limit: 3 }),
+            call(&mut evaluator, "g(3)")
+        );
+        assert_eq!(Object::Lit(Literal::Num(0.0)), call(&mut evaluator, "g(2)"));
+    }
+
+    /// Test for checking if the evaluation is aborted once the deadline
+    /// has passed and if `sleep` does not wait past the deadline
+    #[test]
+    fn test_timeout() {
+        use std::time::Instant;
+
+        let mut lexer = Lexer::new(&"tests/evaluator/fuel.nx".into()).expect("Failed to find file");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            timeout: Some(Duration::ZERO),
+            ..Default::default()
+        });
+        assert_eq!(
+            Object::Err(EvalError::Timeout),
+            evaluator.eval_program(program)
+        );
+
+        evaluator.config.timeout = Some(Duration::from_secs(60));
+        evaluator.reset_deadline();
+        let main = evaluator.get_function("main").expect("Failed to find main");
+        assert_eq!(
+            Object::Lit(Literal::Num(3.0)),
+            evaluator.call_function(&main, Vec::new())
+        );
+
+        // Sleeping past the deadline only waits until the deadline
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            timeout: Some(Duration::from_millis(20)),
+            ..Default::default()
+        });
+        let start = Instant::now();
+        let program = parse_source("sleep(60000)").unwrap();
+        assert_eq!(
+            Object::Err(EvalError::Timeout),
+            evaluator.eval_program(program)
+        );
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        let program = parse_source("var big = 1000000000\nsleep(big * big * big)").unwrap();
+        assert_eq!(
+            Object::Err(EvalError::Overflow("Duration".into())),
+            Evaluator::new().eval_program(program)
+        );
+    }
+
+    /// Test for checking if a program that keeps doubling
+    /// a list fails once it exceeds the memory limit
+    #[test]
+    fn test_memory_limit() {
+        let mut lexer =
+            Lexer::new(&"tests/evaluator/memory.nx".into()).expect("Failed to find file");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            memory_limit: Some(1 << 20),
+            ..Default::default()
+        });
+        assert_eq!(
+            Object::Err(EvalError::OutOfMemory { limit: 1 << 20 }),
+            evaluator.eval_program(program)
+        );
+        assert!(evaluator.allocated() <= 1 << 20);
+        assert!(evaluator.allocated() > 0);
+
+        // Every evaluation starts counting again
+        let list = "var xs = [1, 2, 3, 4, 5, 6, 7, 8]";
+        let mut evaluator = Evaluator::new();
+        evaluator.eval_program(parse_source(list).unwrap());
+        let size = evaluator.allocated();
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            memory_limit: Some(size + size / 2),
+            ..Default::default()
+        });
+        assert!(!matches!(
+            evaluator.eval_program(parse_source(list).unwrap()),
+            Object::Err(_)
+        ));
+        assert!(!matches!(
+            evaluator.eval_program(parse_source("var ys = xs[1..]").unwrap()),
+            Object::Err(_)
+        ));
+        // Assigning an element only counts how much the list grows
+        let program = ["xs[0] = 1"; 8].join("\n");
+        assert!(!matches!(
+            evaluator.eval_program(parse_source(&program).unwrap()),
+            Object::Err(_)
+        ));
+        let program = format!("xs[0] = \"{}\"", "a".repeat(2 * size));
+        assert_eq!(
+            Object::Err(EvalError::OutOfMemory {
+                limit: size + size / 2
+            }),
+            evaluator.eval_program(parse_source(&program).unwrap())
+        );
+
+        // Indexing does not allocate
+        let mut evaluator = Evaluator::new();
+        let program = parse_source("var xs = [[1, 2, 3]]\nxs[0]\nxs[0]").unwrap();
+        evaluator.eval_program(program);
+        let read = evaluator.allocated();
+        let program = parse_source("var ys = [[1, 2, 3]]").unwrap();
+        evaluator.eval_program(program);
+        assert_eq!(read, evaluator.allocated());
+    }
+
+    /// Test for checking if independent evaluators
+    /// can run on multiple threads at once
+    #[test]
+    fn test_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Evaluator>();
+        assert_send_sync::<Object>();
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                thread::spawn(move || {
+                    let mut lexer = Lexer::new(&"tests/evaluator/globals.nx".into())
+                        .expect("Failed to find file");
+                    let program = Parser::new(&mut lexer).parse_program().unwrap();
+                    let mut evaluator = Evaluator::new();
+                    evaluator.eval_program(program);
+                    let main = evaluator.get_function("main").expect("Failed to find main");
+                    let args = vec![
+                        Object::Lit(Literal::Num(i as f64)),
+                        Object::Lit(Literal::Num(1.0)),
+                    ];
+                    evaluator.call_function(&main, args)
+                })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let obj = handle.join().expect("Evaluator thread panicked");
+            assert_eq!(Object::Lit(Literal::Num((i as f64 + 1.0) * 3.0)), obj);
+        }
+    }
+
+    /// Test for checking if functions can run on other
+    /// threads and exchange values over channels
+    #[test]
+    fn test_spawn() {
+        assert_all_true("threads");
+
+        assert_eq!(
+            vec![
+                Object::Lit(Literal::Num(1.0)),
+                Object::Err(EvalError::ThreadFailed(
+                    "The thread was already joined".into()
+                )),
+                Object::Err(EvalError::TypeMismatch {
+                    expected: "Func".into(),
+                    got: "Num".into()
+                }),
+                Object::Err(EvalError::TypeMismatch {
+                    expected: "Channel".into(),
+                    got: "Thread".into()
+                }),
+            ],
+            eval_file("thread_errors")
+        );
+    }
+
+    /// Test for checking if spawned threads use up the fuel
+    /// and memory of the spawning thread and have its deadline
+    #[test]
+    fn test_spawn_limits() {
+        let source = "count :: func(n) {\n    if n == 0 { 0 } else { count(n - 1) }\n}\n";
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            fuel: Some(u64::MAX),
+            ..Default::default()
+        });
+        evaluator.eval_program(parse_source(source).unwrap());
+        let cost = |evaluator: &mut Evaluator, program: &str| {
+            let before = evaluator.remaining_fuel().unwrap();
+            evaluator.eval_program(parse_source(program).unwrap());
+            before - evaluator.remaining_fuel().unwrap()
+        };
+        let count = cost(&mut evaluator, "count(40)");
+        assert!(cost(&mut evaluator, "join(spawn(count, 40))") > count);
+
+        // Six threads that each need a third of the fuel
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            fuel: Some(3 * count),
+            ..Default::default()
+        });
+        let spawns = "var threads = [".to_string() + &["spawn(count, 40)"; 6].join(", ") + "]\n";
+        let joins = (0..6).map(|i| format!("join(threads[{}])", i));
+        let program = source.to_string() + &spawns + &joins.collect::<Vec<_>>().join("\n");
+        assert_eq!(
+            Object::Err(EvalError::OutOfFuel),
+            evaluator.eval_program(parse_source(&program).unwrap())
+        );
+        assert_eq!(Some(0), evaluator.remaining_fuel());
+
+        let mut evaluator = Evaluator::new();
+        evaluator.eval_program(parse_source("[1, 2, 3, 4, 5, 6, 7, 8]").unwrap());
+        let size = evaluator.allocated();
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            memory_limit: Some(size + size / 2),
+            ..Default::default()
+        });
+        let program =
+            "make :: func() { [1, 2, 3, 4, 5, 6, 7, 8] }\nvar xs = make()\njoin(spawn(make))";
+        assert_eq!(
+            Object::Err(EvalError::OutOfMemory {
+                limit: size + size / 2
+            }),
+            evaluator.eval_program(parse_source(program).unwrap())
+        );
+
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            timeout: Some(Duration::from_millis(200)),
+            ..Default::default()
+        });
+        let program = "nap :: func() { sleep(100) }\nsleep(150)\njoin(spawn(nap))";
+        assert_eq!(
+            Object::Err(EvalError::Timeout),
+            evaluator.eval_program(parse_source(program).unwrap())
+        );
+    }
+
+    /// Test for checking if async evaluation yields between statements,
+    /// sleeps without blocking the thread and records the coverage
+    /// like the sync one. Dropping it goes back to the sync mode
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async() {
+        use std::{
+            future::Future,
+            sync::Arc,
+            task::{Context, Poll, Wake},
+            time::Instant,
+        };
+
+        struct ThreadWaker(thread::Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let mut lexer =
+            Lexer::new(&"tests/evaluator/async.nx".into()).expect("Failed to find file");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            coverage: true,
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(evaluator.eval_program_async(program));
+        let mut pending = 0;
+        let obj = loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(obj) => break obj,
+                Poll::Pending => {
+                    pending += 1;
+                    thread::park();
+                }
+            }
+        };
+        assert_eq!(Object::Lit(Literal::Bool(true)), obj);
+        drop(future);
+        assert!(pending >= 4);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(
+            vec![(1, 1), (2, 1), (3, 1), (4, 1)],
+            evaluator.coverage().unwrap().lines().collect::<Vec<_>>()
+        );
+
+        let program = parse_source("1\nsleep(1000)").unwrap();
+        let mut future = Box::pin(evaluator.eval_program_async(program));
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+        drop(future);
+        let start = Instant::now();
+        evaluator.eval_program(parse_source("sleep(20)").unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    /// Test for checking how objects are shown
+    /// to users and in the repl
+    #[test]
+    fn test_display() {
+        let objs = eval_file("display");
+        let shown: Vec<String> = objs.iter().map(|obj| obj.to_string()).collect();
+        assert_eq!(
+            vec![
+                "3",
+                "2.5",
+                "text",
+                "1.50",
+                "none",
+                "[1, \"a\", [true]]",
+                "{\"a\": 1, 2: \"b\"}",
+                "<func(a, b)>",
+                "Error: Index 3 is out of bounds for a sequence of length 1",
+            ],
+            shown
+        );
+        let inspected: Vec<String> = objs.iter().map(Object::inspect).collect();
+        assert_eq!("\"text\"", inspected[2]);
+        assert_eq!("1.50d", inspected[3]);
+    }
+
+    /// Test for checking if types of expressions
+    /// are known without evaluating them
+    #[test]
+    fn test_static_type() {
+        let mut evaluator = Evaluator::new();
+        let program = parse_source("var price = 2.5d\nvar name = \"nexus\"").unwrap();
+        evaluator.eval_program(program);
+
+        let types: Vec<Option<&str>> = [
+            "price * 2",
+            "name[0..2]",
+            "len(name)",
+            "-1 > 2",
+            "true ? 1 : \"a\"",
+            "print(1)",
+            "unknown",
+        ]
+        .iter()
+        .map(|source| match &parse_source(source).unwrap().stmts[..] {
+            [Statement::Expression(expr)] => evaluator.static_type(expr),
+            other => panic!("Expected a single expression, got {:?}", other),
+        })
+        .collect();
+        assert_eq!(
+            vec![
+                Some("Decimal"),
+                Some("Str"),
+                Some("Num"),
+                Some("Bool"),
+                None,
+                None,
+                None
+            ],
+            types
+        );
+    }
+
+    /// Test for checking if observers get notified
+    /// about statements, calls and errors
+    #[test]
+    fn test_observer() {
+        struct Logger(Arc<Mutex<Vec<String>>>);
+
+        impl EvalObserver for Logger {
+            fn on_statement_enter(
+                &mut self,
+                _stmt: &Statement,
+                _line: Option<usize>,
+                _env: &Environment,
+            ) {
+                self.0.lock().unwrap().push("enter".into());
+            }
+
+            fn on_call(&mut self, name: &str, args: &[Object]) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("call {} {}", name, args.len()));
+            }
+
+            fn on_return(&mut self, name: &str, result: &Object) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("return {} {}", name, result));
+            }
+
+            fn on_error(&mut self, err: &EvalError) {
+                self.0.lock().unwrap().push(format!("error {}", err));
+            }
+        }
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut evaluator = Evaluator::new();
+        evaluator.set_observer(Box::new(Logger(Arc::clone(&log))));
+        eval_file_with("observer", &mut evaluator);
+        assert_eq!(
+            vec![
+                "enter",
+                "enter",
+                "call double 1",
+                "enter",
+                "return double 4",
+                "enter",
+                "enter",
+                "error Index 3 is out of bounds for a sequence of length 1",
+            ],
+            *log.lock().unwrap()
+        );
+        assert!(evaluator.take_observer().is_some());
+    }
+
+    /// Test for checking if the profiler counts calls
+    /// and lines and only when profiling is enabled
+    #[test]
+    fn test_profile() {
+        let source = std::fs::read_to_string("tests/evaluator/profile.nx").unwrap();
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            profile: true,
+            ..Default::default()
+        });
+        evaluator.eval_program(parse_source(&source).unwrap());
+        let report = evaluator.profile_report().unwrap();
+
+        assert_eq!(1, report.functions.len());
+        assert_eq!("count", report.functions[0].key);
+        assert_eq!(7, report.functions[0].count);
+        let hits = |line| {
+            report
+                .lines
+                .iter()
+                .find(|entry| entry.key == line)
+                .map(|entry| entry.count)
+        };
+        assert_eq!(Some(1), hits(1));
+        assert_eq!(Some(7), hits(2));
+        assert_eq!(Some(5), hits(3));
+        assert_eq!(Some(2), hits(5));
+        assert_eq!(Some(1), hits(8));
+        assert!(report
+            .functions
+            .windows(2)
+            .all(|pair| pair[0].time >= pair[1].time));
+
+        assert!(Evaluator::new().profile_report().is_none());
+    }
+
+    /// Test for checking if coverage counts statements
+    /// and branches, including those that never ran
+    #[test]
+    fn test_coverage() {
+        let source = std::fs::read_to_string("tests/evaluator/coverage.nx").unwrap();
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            coverage: true,
+            ..Default::default()
+        });
+        evaluator.eval_program(parse_source(&source).unwrap());
+        let coverage = evaluator.coverage().unwrap();
+
+        assert_eq!(
+            vec![(1, 1), (2, 1), (3, 1), (5, 0), (7, 0), (10, 1), (11, 1)],
+            coverage.lines().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(2, 0, 1), (2, 1, 0), (2, 2, 0), (11, 0, 0), (11, 1, 1)],
+            coverage.branches().collect::<Vec<_>>()
+        );
+        let summary = coverage.summary();
+        assert_eq!((5, 7), (summary.lines_hit, summary.lines_total));
+        assert_eq!((2, 5), (summary.branches_hit, summary.branches_total));
+
+        let lcov = coverage.to_lcov("coverage.nx");
+        assert!(lcov.starts_with("TN:\nSF:coverage.nx\n"));
+        assert!(lcov.contains("BRDA:2,0,1,0\n"));
+        assert!(lcov.contains("DA:5,0\n"));
+        assert!(lcov.ends_with("LF:7\nLH:5\nend_of_record\n"));
+
+        assert!(Evaluator::new().coverage().is_none());
+    }
+
+    /// Test for checking if `print` and `input` use the io
+    /// of the evaluator, including on other threads
+    #[test]
+    fn test_io() {
+        let io = Arc::new(BufferedIo::new(&["nexus", "abc"]));
+        let mut evaluator = Evaluator::new();
+        evaluator.set_io(io.clone());
+        eval_file_with("io", &mut evaluator);
+        assert_eq!("hello nexus\n\n4\ntrue\n", io.take_output());
+        assert_eq!("", io.take_output());
+    }
+
+    /// Test for checking if restoring a snapshot undoes declarations
+    /// and if the snapshot is not changed by later evaluations
+    #[test]
+    fn test_snapshot() {
+        let eval = |evaluator: &mut Evaluator, source: &str| {
+            let mut lexer = Lexer::from_source(source);
+            evaluator.eval_program(Parser::new(&mut lexer).parse_program().unwrap())
+        };
+        let mut evaluator = Evaluator::new();
+        eval(&mut evaluator, "var base = 1");
+        let snapshot = evaluator.env.snapshot();
+
+        let obj = eval(&mut evaluator, "var base = 2\nvar extra = 3\n[1][5]");
+        assert!(matches!(
+            obj,
+            Object::Err(EvalError::IndexOutOfBounds { .. })
+        ));
+        assert_eq!(2, evaluator.globals().count());
+        evaluator.env.restore(&snapshot);
+        let globals: Vec<(String, Object)> = evaluator.globals().collect();
+        assert_eq!(
+            vec![("base".to_string(), Object::Lit(Literal::Num(1.0)))],
+            globals
+        );
+
+        // Restoring twice starts from the same state again
+        eval(&mut evaluator, "var base = 4");
+        evaluator.env.restore(&snapshot);
+        assert_eq!(Object::Lit(Literal::Num(1.0)), eval(&mut evaluator, "base"));
+    }
+
+    /// Test for checking if single expressions can use the variables
+    /// of the evaluator without changing them, follow its configuration
+    /// and return syntax and runtime errors alike
+    #[test]
+    fn test_eval_expression_source() {
+        let mut evaluator = Evaluator::with_config(EvalConfig {
+            fuel: Some(100),
+            ..Default::default()
+        });
+        evaluator.env.set(
+            "price".into(),
+            EnvObj::new(Object::Lit(Literal::Num(4.0)), false),
+        );
+        assert_eq!(
+            Ok(Object::Lit(Literal::Num(9.0))),
+            evaluator.eval_expression_source("price * 2 + 1")
+        );
+        assert_eq!(
+            Err(SourceError::Eval(EvalError::UndefinedVariable {
+                name: "tax".into(),
+                suggestion: None,
+            })),
+            evaluator.eval_expression_source("price * tax")
+        );
+        assert_eq!(
+            Err(SourceError::Eval(EvalError::TypeMismatch {
+                expected: "Num".into(),
+                got: "Str".into(),
+            })),
+            evaluator.eval_expression_source("price + \"a\"")
+        );
+        assert_eq!(
+            ErrorCode::UnexpectedToken,
+            evaluator
+                .eval_expression_source("price )")
+                .unwrap_err()
+                .code()
+        );
+        assert_eq!(
+            Ok(Object::Lit(Literal::Num(0.0))),
+            evaluator.eval_expression_source("price = 0")
+        );
+        assert_eq!(
+            Ok(Object::Lit(Literal::Num(4.0))),
+            evaluator.eval_expression_source("price")
+        );
+        assert_eq!(1, evaluator.env.globals().count());
+        assert_eq!(
+            Err(SourceError::Eval(EvalError::OutOfFuel)),
+            evaluator.eval_expression_source(&format!("{}1", "price + ".repeat(100)))
+        );
+    }
+
+    /// Test for checking if using undeclared names, calling values that are
+    /// not functions, mixing types and loops are errors instead of panics
+    /// and if errors have their codes
+    #[test]
+    fn test_error_codes() {
+        let eval = |source: &str| {
+            let mut lexer = Lexer::from_source(source);
+            Evaluator::new().eval_program(Parser::new(&mut lexer).parse_program().unwrap())
+        };
+        let err = |source: &str| match eval(source) {
+            Object::Err(err) => err,
+            other => panic!("Expected an error, got {}", other),
+        };
+        assert_eq!(
+            EvalError::UndefinedVariable {
+                name: "y".into(),
+                suggestion: None,
+            },
+            err("1 + y")
+        );
+        assert_eq!(
+            EvalError::UndefinedVariable {
+                name: "f".into(),
+                suggestion: None,
+            },
+            err("f(1)")
+        );
+        assert_eq!(ErrorCode::UndefinedVariable, err("f(1)").code());
+        assert_eq!("E0203", err("f(1)").code().code());
+        assert_eq!(ErrorCode::IndexOutOfBounds, err("[1][2]").code());
+        assert_eq!(ErrorCode::NotCallable, err("var x = 1\nx()").code());
+        assert_eq!(
+            EvalError::NotCallable("Num".into()),
+            err("f :: func() { 1 }\nf()()")
+        );
+        assert_eq!(
+            EvalError::TypeMismatch {
+                expected: "Num".into(),
+                got: "Str".into(),
+            },
+            err("1 + \"a\"")
+        );
+        assert_eq!(ErrorCode::TypeMismatch, err("-\"x\"").code());
+        assert_eq!(ErrorCode::TypeMismatch, err("!1").code());
+        assert_eq!(ErrorCode::DivisionByZero, err("1.0d / 0.0d").code());
+        assert_eq!(
+            EvalError::Unsupported("Loops".into()),
+            err("loop item in [1, 2] { item }")
+        );
+        assert_eq!(ErrorCode::Unsupported, err("loop true { 1 }").code());
+        assert_eq!(
+            EvalError::Unsupported("Return statements".into()),
+            err("f :: func() { return 1 }\nf()")
+        );
+    }
+
+    /// Test for checking if builtins that a profile does not allow
+    /// can be replaced by functions of the program
+    #[test]
+    fn test_builtin_profiles() {
+        let profiles = [
+            BuiltinProfile::NoIo,
+            BuiltinProfile::Pure,
+            BuiltinProfile::Only(vec!["len".into()]),
+        ];
+        for profile in profiles {
+            let mut evaluator = Evaluator::with_config(EvalConfig {
+                builtins: profile.clone(),
+                ..Default::default()
+            });
+            for obj in eval_file_with("sandbox", &mut evaluator) {
+                assert!(
+                    matches!(obj, Object::Lit(Literal::Bool(true))),
+                    "{} instead of true with {:?}",
+                    obj,
+                    profile
+                );
+            }
+        }
+
+        assert!(BuiltinProfile::Full.allows("double"));
+        assert!(BuiltinProfile::NoIo.allows("spawn"));
+        assert!(!BuiltinProfile::NoIo.allows("double"));
+        assert!(!BuiltinProfile::Pure.allows("sleep"));
+        assert!(BuiltinProfile::Only(vec!["double".into()]).allows("double"));
+    }
+
+    /// Test for checking if functions of the host can be called,
+    /// including on other threads, and if their errors are returned
+    #[test]
+    fn test_register_builtin() {
+        let mut evaluator = Evaluator::new();
+        evaluator.register_builtin("double", |args| match args {
+            [Object::Lit(Literal::Num(num))] => Ok(Object::Lit(Literal::Num(num * 2.0))),
+            _ => Err(EvalError::HostFailed("Expected a number".into())),
+        });
+        for (i, obj) in eval_file_with("host", &mut evaluator)
+            .into_iter()
+            .enumerate()
+        {
+            assert!(
+                matches!(obj, Object::Lit(Literal::Bool(true))),
+                "Statement {} of host.nx evaluated to {} instead of true",
+                i + 1,
+                obj
+            );
+        }
+
+        let mut lexer = Lexer::from_source("double(\"a\")");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        assert_eq!(
+            Object::Err(EvalError::HostFailed("Expected a number".into())),
+            evaluator.eval_program(program)
+        );
+    }
+
+    /// Asserts that every expression statement in the file evaluates to `true`
+    fn assert_all_true(test: &str) {
+        for (i, obj) in eval_file(test).into_iter().enumerate() {
+            assert!(
+                matches!(obj, Object::Lit(Literal::Bool(true))),
+                "Statement {} of {}.nx evaluated to {} instead of true",
+                i + 1,
+                test,
+                obj
+            );
+        }
+    }
+
+    /// Evaluates every statement of the file and
+    /// returns the values of the expression statements
+    fn eval_file(test: &str) -> Vec<Object> {
+        eval_file_with(test, &mut Evaluator::new())
+    }
+
+    fn eval_file_with(test: &str, evaluator: &mut Evaluator) -> Vec<Object> {
+        let mut lexer =
+            Lexer::new(&format!("tests/evaluator/{}.nx", test)).expect("Failed to find file");
+        let mut parser = Parser::new(&mut lexer);
+        let mut objs = Vec::new();
+        while let Some(stmt) = parser.parse_stmt().expect("Failed to parse") {
+            let is_expr = matches!(stmt, Statement::Expression(_));
+            let obj = evaluator.eval_stmt(stmt);
+            if is_expr {
+                objs.push(obj);
+            }
+            parser.next_token().expect("Failed to parse");
+        }
+        objs
     }
-}
\ No newline at end of file
+}
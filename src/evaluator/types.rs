@@ -0,0 +1,66 @@
+//! Static approximation of the types of expressions.
+//!
+//! Nothing gets evaluated, so there are no side effects. Variables are looked
+//! up in the environment, calls of user defined functions cannot be typed
+//! without running them and result in `None`
+
+use crate::parser::ast::{Expression, InfixOp, PrefixOp};
+
+use super::{objects::Object, Evaluator};
+
+impl Evaluator {
+    /// Name of the type the expression would evaluate to,
+    /// or `None` if it cannot be known without evaluating it
+    pub fn static_type(&self, expr: &Expression) -> Option<&'static str> {
+        Some(match expr {
+            Expression::Literal(lit) => Object::Lit(lit.clone()).type_name(),
+            Expression::None => "None",
+            Expression::Ident(ident) => self.env.get(&ident.0)?.obj.type_name(),
+            Expression::List(_) => "List",
+            Expression::Slice(slice) => self.static_type(&slice.list)?,
+            Expression::Hash(_) => "Hash",
+            Expression::Func(_) => "Func",
+            Expression::Prefix(prefix) => match prefix.op {
+                PrefixOp::Not => "Bool",
+                PrefixOp::Pos | PrefixOp::Neg => self.static_type(&prefix.val)?,
+            },
+            Expression::Infix(infix) => match infix.op {
+                InfixOp::Eq
+                | InfixOp::NEq
+                | InfixOp::GT
+                | InfixOp::LT
+                | InfixOp::GTEq
+                | InfixOp::LTEq
+                | InfixOp::In => "Bool",
+                InfixOp::Add | InfixOp::Sub | InfixOp::Mul | InfixOp::Div => {
+                    match (
+                        self.static_type(&infix.left)?,
+                        self.static_type(&infix.right)?,
+                    ) {
+                        ("Decimal", "Num" | "Decimal") | ("Num", "Decimal") => "Decimal",
+                        ("Num", "Num") => "Num",
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            },
+            Expression::Assign(assign) => self.static_type(&assign.val)?,
+            Expression::Ternary(ternary) => {
+                let then = self.static_type(&ternary.then)?;
+                if then != self.static_type(&ternary.alt)? {
+                    return None;
+                }
+                then
+            }
+            // Elements of lists and hashes can have any type
+            Expression::Index(index) if self.static_type(&index.list) == Some("Str") => "Str",
+            Expression::Call(call) => match call.ident.as_ref() {
+                Expression::Ident(ident) if ident.0 == "len" || ident.0 == "compare" => "Num",
+                Expression::Ident(ident) if ident.0 == "input" => "Str",
+                Expression::Ident(ident) if ident.0 == "sort" || ident.0 == "sort_by" => "List",
+                _ => return None,
+            },
+            _ => return None,
+        })
+    }
+}
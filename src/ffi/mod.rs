@@ -0,0 +1,344 @@
+//! C interface for embedding nexus in other languages, behind the `ffi` feature.
+//!
+//! `cargo build --release --features ffi` builds a shared library
+//! that can be loaded from C or, for example, from Python with ctypes:
+//!
+//! ```text
+//! NexusEvaluator *evaluator = nexus_evaluator_new();
+//! if (nexus_eval(evaluator, "1 + 2")) {
+//!     char *result = nexus_result_json(evaluator);
+//!     // result is "3.0"
+//!     nexus_string_free(result);
+//! }
+//! nexus_evaluator_free(evaluator);
+//! ```
+//!
+//! Strings are utf-8 and end with a nul character. Strings returned by
+//! these functions belong to the caller and have to be freed with
+//! [`nexus_string_free`]. Values are passed as JSON: numbers are numbers,
+//! decimals are strings to keep their precision, `none` is `null` and
+//! hashes are objects with their keys as strings. Functions, threads
+//! and channels cannot be represented
+//!
+//! Panics of the interpreter never cross the boundary, syntax errors
+//! and panics are reported like any other error by [`nexus_last_error`].
+//! [`nexus_last_error_code`] tells them apart by their code
+
+mod tests;
+
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_void, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    codes::ErrorCode,
+    evaluator::{
+        errors::EvalError,
+        objects::{HashKey, HashObj, ListObj, Object},
+        Evaluator,
+    },
+    parser::{ast::Literal, parse_source},
+    util,
+};
+
+/// Evaluator together with the outcome of the last [`nexus_eval`]
+pub struct NexusEvaluator {
+    evaluator: Evaluator,
+    result: Option<Object>,
+    error: Option<String>,
+    error_code: Option<ErrorCode>,
+}
+
+/// Function of the host that can be called by programs,
+/// see [`nexus_register_builtin`].
+///
+/// Receives the `user_data` it was registered with and the arguments
+/// as a JSON array. Returns the result as JSON, or null if the call
+/// failed. The returned string stays owned by the host and only has
+/// to be valid until the callback is called again
+pub type NexusCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, args_json: *const c_char) -> *const c_char;
+
+/// Creates an evaluator with the default config.
+/// It has to be freed with [`nexus_evaluator_free`]
+#[no_mangle]
+pub extern "C" fn nexus_evaluator_new() -> *mut NexusEvaluator {
+    Box::into_raw(Box::new(NexusEvaluator {
+        evaluator: Evaluator::new(),
+        result: None,
+        error: None,
+        error_code: None,
+    }))
+}
+
+/// Frees the evaluator. Does nothing if it is null
+///
+/// # Safety
+///
+/// `evaluator` has to be null or come from [`nexus_evaluator_new`]
+/// and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn nexus_evaluator_free(evaluator: *mut NexusEvaluator) {
+    if !evaluator.is_null() {
+        drop(Box::from_raw(evaluator));
+    }
+}
+
+/// Parses and evaluates the source code. Declarations stay
+/// in the evaluator, so later calls can use them.
+/// A failed evaluation is undone completely, including
+/// the declarations that ran before the error.
+/// Returns whether the evaluation succeeded
+///
+/// # Safety
+///
+/// `evaluator` has to come from [`nexus_evaluator_new`]
+/// and `source` has to be a string that ends with a nul character
+#[no_mangle]
+pub unsafe extern "C" fn nexus_eval(evaluator: *mut NexusEvaluator, source: *const c_char) -> bool {
+    let Some(evaluator) = evaluator.as_mut() else {
+        return false;
+    };
+    let outcome = match read_str(source) {
+        Some(source) => evaluator.eval(source),
+        None => Err(("The source code is not valid utf-8".into(), None)),
+    };
+    let success = outcome.is_ok();
+    (evaluator.result, evaluator.error, evaluator.error_code) = match outcome {
+        Ok(obj) => (Some(obj), None, None),
+        Err((err, code)) => (None, Some(err), code),
+    };
+    success
+}
+
+/// Value of the last successful evaluation as it would be printed.
+/// Null if the last evaluation failed
+///
+/// # Safety
+///
+/// `evaluator` has to come from [`nexus_evaluator_new`]
+#[no_mangle]
+pub unsafe extern "C" fn nexus_result_string(evaluator: *const NexusEvaluator) -> *mut c_char {
+    match evaluator
+        .as_ref()
+        .and_then(|evaluator| evaluator.result.as_ref())
+    {
+        Some(obj) => into_c_string(obj.to_string()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Value of the last successful evaluation as JSON.
+/// Null if the last evaluation failed or the value cannot be represented
+///
+/// # Safety
+///
+/// `evaluator` has to come from [`nexus_evaluator_new`]
+#[no_mangle]
+pub unsafe extern "C" fn nexus_result_json(evaluator: *const NexusEvaluator) -> *mut c_char {
+    match evaluator
+        .as_ref()
+        .and_then(|evaluator| to_json(evaluator.result.as_ref()?))
+    {
+        Some(json) => into_c_string(json.to_string()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Message of the error of the last evaluation.
+/// Null if the last evaluation succeeded
+///
+/// # Safety
+///
+/// `evaluator` has to come from [`nexus_evaluator_new`]
+#[no_mangle]
+pub unsafe extern "C" fn nexus_last_error(evaluator: *const NexusEvaluator) -> *mut c_char {
+    match evaluator
+        .as_ref()
+        .and_then(|evaluator| evaluator.error.as_ref())
+    {
+        Some(err) => into_c_string(err.clone()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Code of the error of the last evaluation, like `E0101`.
+/// Null if the last evaluation succeeded or the error
+/// has no code, like invalid utf-8 or a panic
+///
+/// # Safety
+///
+/// `evaluator` has to come from [`nexus_evaluator_new`]
+#[no_mangle]
+pub unsafe extern "C" fn nexus_last_error_code(evaluator: *const NexusEvaluator) -> *mut c_char {
+    match evaluator
+        .as_ref()
+        .and_then(|evaluator| evaluator.error_code)
+    {
+        Some(code) => into_c_string(code.code().into()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Makes the callback callable under the name, see [`NexusCallback`].
+/// Returns false if the name is not valid utf-8
+///
+/// # Safety
+///
+/// `evaluator` has to come from [`nexus_evaluator_new`] and `name` has to
+/// be a string that ends with a nul character. The callback can be
+/// called from any thread, since programs can call it inside of `spawn`
+#[no_mangle]
+pub unsafe extern "C" fn nexus_register_builtin(
+    evaluator: *mut NexusEvaluator,
+    name: *const c_char,
+    callback: NexusCallback,
+    user_data: *mut c_void,
+) -> bool {
+    let (Some(evaluator), Some(name)) = (evaluator.as_mut(), read_str(name)) else {
+        return false;
+    };
+    let callback = Callback {
+        func: callback,
+        user_data,
+        name: name.into(),
+    };
+    evaluator
+        .evaluator
+        .register_builtin(name, move |args| callback.call(args));
+    true
+}
+
+/// Frees a string that was returned by one of these functions.
+/// Does nothing if it is null
+///
+/// # Safety
+///
+/// `string` has to be null or come from one of these functions
+/// and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn nexus_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+impl NexusEvaluator {
+    /// Returns the message and code of the error if the evaluation failed
+    fn eval(&mut self, source: &str) -> Result<Object, (String, Option<ErrorCode>)> {
+        let program = parse_source(source).map_err(|err| (err.to_string(), Some(err.code())))?;
+        let evaluator = &mut self.evaluator;
+        let snapshot = evaluator.env.snapshot();
+        let ret = match panic::catch_unwind(AssertUnwindSafe(|| evaluator.eval_program(program))) {
+            Ok(Object::Err(err)) => Err((err.to_string(), Some(err.code()))),
+            Ok(obj) => Ok(obj),
+            Err(err) => Err((
+                util::panic_message(&*err).unwrap_or_else(|| "Unknown error".into()),
+                None,
+            )),
+        };
+        if ret.is_err() {
+            evaluator.env.restore(&snapshot);
+        }
+        ret
+    }
+}
+
+struct Callback {
+    func: NexusCallback,
+    user_data: *mut c_void,
+    name: String,
+}
+
+// The host promises that the callback and its data can be used from any thread
+unsafe impl Send for Callback {}
+unsafe impl Sync for Callback {}
+
+impl Callback {
+    fn call(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let args = args
+            .iter()
+            .map(|arg| {
+                to_json(arg).ok_or_else(|| {
+                    EvalError::HostFailed(format!(
+                        "Cannot pass a value of type {} to the host function {}",
+                        arg.type_name(),
+                        self.name
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let args = CString::new(Value::Array(args).to_string())
+            .map_err(|_| self.failed("received a nul character"))?;
+        // SAFETY: the host promised that the callback accepts these arguments
+        let ret = unsafe { (self.func)(self.user_data, args.as_ptr()) };
+        if ret.is_null() {
+            return Err(self.failed("failed"));
+        }
+        // SAFETY: the host promised to return a string that ends with a nul character
+        let ret = unsafe { CStr::from_ptr(ret) };
+        let json = ret
+            .to_str()
+            .ok()
+            .and_then(|ret| serde_json::from_str(ret).ok())
+            .ok_or_else(|| self.failed("returned invalid JSON"))?;
+        Ok(from_json(json))
+    }
+
+    fn failed(&self, reason: &str) -> EvalError {
+        EvalError::HostFailed(format!("The host function {} {}", self.name, reason))
+    }
+}
+
+unsafe fn read_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+    CStr::from_ptr(string).to_str().ok()
+}
+
+/// Null if the string contains a nul character
+fn into_c_string(string: String) -> *mut c_char {
+    CString::new(string).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+fn to_json(obj: &Object) -> Option<Value> {
+    Some(match obj {
+        Object::Lit(Literal::Str(str)) => Value::String(str.clone()),
+        Object::Lit(Literal::Num(num)) => Value::Number(Number::from_f64(*num)?),
+        Object::Lit(Literal::Decimal(decimal)) => Value::String(decimal.to_string()),
+        Object::Lit(Literal::Bool(bool)) => Value::Bool(*bool),
+        Object::None | Object::Void => Value::Null,
+        Object::List(list) => Value::Array(list.list.iter().map(to_json).collect::<Option<_>>()?),
+        Object::Hash(hash) => Value::Object(
+            hash.map
+                .iter()
+                .map(|(key, val)| Some((key.to_string(), to_json(val)?)))
+                .collect::<Option<Map<_, _>>>()?,
+        ),
+        _ => return None,
+    })
+}
+
+fn from_json(json: Value) -> Object {
+    match json {
+        Value::Null => Object::None,
+        Value::Bool(bool) => Object::Lit(Literal::Bool(bool)),
+        Value::Number(num) => Object::Lit(Literal::Num(num.as_f64().unwrap_or(f64::NAN))),
+        Value::String(str) => Object::Lit(Literal::Str(str)),
+        Value::Array(list) => Object::List(ListObj {
+            list: list.into_iter().map(from_json).collect(),
+        }),
+        Value::Object(map) => Object::Hash(HashObj {
+            map: map
+                .into_iter()
+                .map(|(key, val)| (HashKey::Str(key), from_json(val)))
+                .collect::<HashMap<_, _>>(),
+        }),
+    }
+}
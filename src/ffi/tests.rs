@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{c_char, c_void, CStr, CString},
+        ptr,
+    };
+
+    use crate::ffi::{
+        nexus_eval, nexus_evaluator_free, nexus_evaluator_new, nexus_last_error,
+        nexus_last_error_code, nexus_register_builtin, nexus_result_json, nexus_result_string,
+        nexus_string_free, NexusEvaluator,
+    };
+
+    /// Test for checking if values and errors are returned as strings,
+    /// errors together with their code
+    /// and if declarations are kept between evaluations, unless they failed
+    #[test]
+    fn test_eval() {
+        let evaluator = nexus_evaluator_new();
+        unsafe {
+            assert!(eval(evaluator, "var xs = [1, \"a\", none]"));
+            assert!(eval(evaluator, "xs"));
+            assert_eq!(
+                Some("[1.0,\"a\",null]".into()),
+                take(nexus_result_json(evaluator))
+            );
+            assert_eq!(None, take(nexus_last_error(evaluator)));
+            assert_eq!(None, take(nexus_last_error_code(evaluator)));
+
+            assert!(eval(evaluator, "{\"a\": 1.5d}"));
+            assert_eq!(
+                Some("{\"a\":\"1.5\"}".into()),
+                take(nexus_result_json(evaluator))
+            );
+            assert!(eval(evaluator, "\"text\""));
+            assert_eq!(Some("text".into()), take(nexus_result_string(evaluator)));
+
+            assert!(!eval(evaluator, "xs[5]"));
+            assert_eq!(None, take(nexus_result_json(evaluator)));
+            assert_eq!(
+                Some("Index 5 is out of bounds for a sequence of length 3".into()),
+                take(nexus_last_error(evaluator))
+            );
+            assert_eq!(Some("E0304".into()), take(nexus_last_error_code(evaluator)));
+            assert!(!eval(evaluator, "var = 1"));
+            assert!(take(nexus_last_error(evaluator)).is_some());
+            assert_eq!(Some("E0101".into()), take(nexus_last_error_code(evaluator)));
+
+            // Declarations of a failed evaluation are undone
+            assert!(!eval(evaluator, "f :: func() { 1 }\nf()\nxs[5]"));
+            assert!(eval(evaluator, "f :: func() { 2 }"));
+            assert!(!eval(evaluator, "g :: func() { return 1 }\ng()"));
+            assert_eq!(
+                Some("Return statements are not supported yet".into()),
+                take(nexus_last_error(evaluator))
+            );
+            assert_eq!(Some("E0505".into()), take(nexus_last_error_code(evaluator)));
+            assert!(eval(evaluator, "g :: func() { 2 }"));
+
+            nexus_evaluator_free(evaluator);
+        }
+    }
+
+    /// Test for checking if callbacks of the host can be called
+    /// with their arguments and user data
+    #[test]
+    fn test_register_builtin() {
+        unsafe extern "C" fn sum(user_data: *mut c_void, args: *const c_char) -> *const c_char {
+            let ret = &mut *(user_data as *mut CString);
+            let args: Vec<f64> = serde_json::from_slice(CStr::from_ptr(args).to_bytes()).unwrap();
+            *ret = CString::new(args.iter().sum::<f64>().to_string()).unwrap();
+            ret.as_ptr()
+        }
+        unsafe extern "C" fn fail(_: *mut c_void, _: *const c_char) -> *const c_char {
+            ptr::null()
+        }
+
+        let evaluator = nexus_evaluator_new();
+        let mut ret = CString::default();
+        unsafe {
+            let name = CString::new("sum").unwrap();
+            let user_data = &mut ret as *mut CString as *mut c_void;
+            assert!(nexus_register_builtin(
+                evaluator,
+                name.as_ptr(),
+                sum,
+                user_data
+            ));
+            let name = CString::new("fail").unwrap();
+            assert!(nexus_register_builtin(
+                evaluator,
+                name.as_ptr(),
+                fail,
+                ptr::null_mut()
+            ));
+
+            assert!(eval(evaluator, "sum(1, 2, 3) == 6"));
+            assert_eq!(Some("true".into()), take(nexus_result_json(evaluator)));
+            assert!(!eval(evaluator, "fail()"));
+            assert_eq!(
+                Some("The host function fail failed".into()),
+                take(nexus_last_error(evaluator))
+            );
+            nexus_evaluator_free(evaluator);
+        }
+    }
+
+    unsafe fn eval(evaluator: *mut NexusEvaluator, source: &str) -> bool {
+        let source = CString::new(source).unwrap();
+        nexus_eval(evaluator, source.as_ptr())
+    }
+
+    /// Copies and frees a string returned by the interface
+    unsafe fn take(string: *mut c_char) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let copy = CStr::from_ptr(string).to_str().unwrap().to_string();
+        nexus_string_free(string);
+        Some(copy)
+    }
+}
@@ -0,0 +1,320 @@
+//! Formatter that prints a program as canonical source code.
+//!
+//! - Statements are indented by four spaces per block
+//! - Binary operators are surrounded by single spaces
+//! - Variables use the `var`/`const` keywords,
+//!   except constant functions which use `name :: func(...)`
+//! - Top level functions are separated from other statements by an empty line
+//!
+//! Doc comments and annotations are kept above their declaration. Other comments
+//! are dropped by the lexer and are therefore lost.
+
+mod tests;
+
+use crate::parser::{
+    ast::{
+        Annotation, BlockStmt, Expression, IfExpr, InfixExpr, InfixOp, Literal, LoopExpr,
+        OptionallyTypedIdent, PrefixOp, Program, Statement, VarStmt,
+    },
+    parse_source, SyntaxError,
+};
+
+const INDENT: &str = "    ";
+
+/// Parses the source code and formats it
+pub fn format_source(source: &str) -> Result<String, SyntaxError> {
+    Ok(format_program(&parse_source(source)?))
+}
+
+pub fn format_program(program: &Program) -> String {
+    let mut formatter = Formatter::default();
+    let mut prev_was_func = false;
+    for (i, stmt) in program.stmts.iter().enumerate() {
+        let is_func = is_func_decl(stmt);
+        if i > 0 && (is_func || prev_was_func) {
+            formatter.out.push('\n');
+        }
+        formatter.stmt(stmt);
+        formatter.out.push('\n');
+        prev_was_func = is_func;
+    }
+    formatter.out
+}
+
+/// Formats a single statement, blocks inside of it span multiple lines
+pub fn format_stmt(stmt: &Statement) -> String {
+    let mut formatter = Formatter::default();
+    formatter.stmt(stmt);
+    formatter.out
+}
+
+fn is_func_decl(stmt: &Statement) -> bool {
+    matches!(
+        stmt,
+        Statement::Variable(VarStmt {
+            val: Expression::Func(_),
+            ..
+        })
+    )
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    depth: usize,
+}
+
+impl Formatter {
+    fn stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Variable(var) => {
+                for doc in var.docs.iter().flat_map(|docs| docs.lines()) {
+                    match doc.is_empty() {
+                        true => self.out.push_str("///\n"),
+                        false => self.out.push_str(&format!("/// {}\n", doc)),
+                    }
+                    self.out.push_str(&INDENT.repeat(self.depth));
+                }
+                for annotation in &var.annotations {
+                    self.annotation(annotation);
+                    self.out.push('\n');
+                    self.out.push_str(&INDENT.repeat(self.depth));
+                }
+                if var.is_const && var.name._type.is_none() && is_func_decl(stmt) {
+                    self.out.push_str(&format!("{} :: ", var.name.ident));
+                } else {
+                    self.out
+                        .push_str(if var.is_const { "const " } else { "var " });
+                    self.typed_ident(&var.name);
+                    self.out.push_str(" = ");
+                }
+                self.expr(&var.val);
+            }
+            Statement::Return(ret) => {
+                self.out.push_str("return");
+                if let Some(val) = &ret.val {
+                    self.out.push(' ');
+                    self.expr(val);
+                }
+            }
+            Statement::Break(br) => {
+                self.out.push_str("break");
+                if let Some(label) = &br.label {
+                    self.out.push_str(&format!(" {}", label));
+                }
+            }
+            Statement::Local(local) => {
+                self.out.push_str("local ");
+                self.stmt(&local.val);
+            }
+            Statement::Use(_use) => self.out.push_str(&format!("use {}", _use.import)),
+            Statement::Expression(expr) => self.expr(expr),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Ident(ident) => self.out.push_str(&ident.0),
+            Expression::Literal(lit) => self.literal(lit),
+            Expression::Prefix(prefix) => {
+                self.out.push_str(match prefix.op {
+                    PrefixOp::Pos => "+",
+                    PrefixOp::Neg => "-",
+                    PrefixOp::Not => "!",
+                });
+                self.expr(&prefix.val);
+            }
+            Expression::Infix(infix) => self.infix(infix),
+            Expression::Assign(assign) => {
+                self.expr(&assign.target);
+                self.out.push_str(" = ");
+                self.expr(&assign.val);
+            }
+            Expression::Ternary(ternary) => {
+                self.expr(&ternary.cond);
+                self.out.push_str(" ? ");
+                self.expr(&ternary.then);
+                self.out.push_str(" : ");
+                self.expr(&ternary.alt);
+            }
+            Expression::Index(index) => {
+                self.expr(&index.list);
+                self.out.push_str(if index.optional { "?[" } else { "[" });
+                self.expr(&index.index);
+                self.out.push(']');
+            }
+            Expression::Field(field) => {
+                self.expr(&field.obj);
+                self.out.push_str(if field.optional { "?." } else { "." });
+                self.out.push_str(&field.field.0);
+            }
+            Expression::Slice(slice) => {
+                self.expr(&slice.list);
+                self.out.push_str(if slice.optional { "?[" } else { "[" });
+                if let Some(start) = &slice.start {
+                    self.expr(start);
+                }
+                self.out.push_str("..");
+                if let Some(end) = &slice.end {
+                    self.expr(end);
+                }
+                self.out.push(']');
+            }
+            Expression::Call(call) => {
+                self.expr(&call.ident);
+                self.out.push('(');
+                self.list(&call.args);
+                self.out.push(')');
+            }
+            Expression::List(list) => {
+                self.out.push('[');
+                self.list(&list.list);
+                self.out.push(']');
+            }
+            Expression::Hash(hash) => {
+                self.out.push('{');
+                for (i, (key, val)) in hash.pairs.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expr(key);
+                    self.out.push_str(": ");
+                    self.expr(val);
+                }
+                self.out.push('}');
+            }
+            Expression::None => self.out.push_str("none"),
+            Expression::If(_if) => self.if_expr(_if),
+            Expression::Func(func) => {
+                self.out.push_str("func(");
+                for (i, arg) in func.args.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.typed_ident(arg);
+                }
+                self.out.push(')');
+                if let Some(ret_type) = &func.ret_type {
+                    self.out.push_str(&format!(": {}", ret_type));
+                }
+                self.out.push(' ');
+                self.block(&func.block);
+            }
+            Expression::Block(block) => self.block(block),
+            Expression::Loop(_loop) => self.loop_expr(_loop),
+            Expression::When(_) => todo!(),
+            Expression::Annotation(annotated) => {
+                for annotation in &annotated.annotations {
+                    self.annotation(annotation);
+                    self.out.push(' ');
+                }
+                self.expr(&annotated.val);
+            }
+            Expression::Struct(_) => todo!(),
+            Expression::Enum(_) => todo!(),
+        }
+    }
+
+    fn literal(&mut self, lit: &Literal) {
+        match lit {
+            Literal::Str(str) => self.out.push_str(&format!("\"{}\"", str)),
+            Literal::Decimal(dec) => self.out.push_str(&format!("{}d", dec)),
+            Literal::Num(_) | Literal::Bool(_) => self.out.push_str(&lit.to_string()),
+        }
+    }
+
+    fn infix(&mut self, infix: &InfixExpr) {
+        self.expr(&infix.left);
+        self.out.push_str(match infix.op {
+            InfixOp::Add => " + ",
+            InfixOp::Sub => " - ",
+            InfixOp::Mul => " * ",
+            InfixOp::Div => " / ",
+            InfixOp::Eq => " == ",
+            InfixOp::NEq => " != ",
+            InfixOp::GT => " > ",
+            InfixOp::LT => " < ",
+            InfixOp::GTEq => " >= ",
+            InfixOp::LTEq => " <= ",
+            InfixOp::As => " as ",
+            InfixOp::In => " in ",
+            InfixOp::Range => "..",
+            InfixOp::NullCoalesce => " ?? ",
+        });
+        self.expr(&infix.right);
+    }
+
+    fn if_expr(&mut self, _if: &IfExpr) {
+        for (i, branch) in _if.branches.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(" else ");
+            }
+            self.out.push_str("if ");
+            self.expr(&branch.cond);
+            self.out.push(' ');
+            self.block(&branch.block);
+        }
+        if let Some(alt) = &_if.alt {
+            self.out.push_str(" else ");
+            self.block(alt);
+        }
+    }
+
+    /// The parser does not produce `else` branches of loops yet,
+    /// so only the loop itself is printed
+    fn loop_expr(&mut self, _loop: &LoopExpr) {
+        self.out.push_str("loop ");
+        if let Some(ident) = &_loop.ident {
+            self.out.push_str(&format!("{} in ", ident));
+        }
+        if let Some(cond) = &_loop.cond {
+            self.expr(cond);
+            self.out.push(' ');
+        }
+        self.block(&_loop.block);
+    }
+
+    /// Puts every statement of the block on its own line
+    fn block(&mut self, block: &BlockStmt) {
+        if block.stmts.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push_str("{\n");
+        self.depth += 1;
+        for stmt in &block.stmts {
+            self.out.push_str(&INDENT.repeat(self.depth));
+            self.stmt(stmt);
+            self.out.push('\n');
+        }
+        self.depth -= 1;
+        self.out.push_str(&INDENT.repeat(self.depth));
+        self.out.push('}');
+    }
+
+    fn list(&mut self, exprs: &[Expression]) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expr(expr);
+        }
+    }
+
+    fn annotation(&mut self, annotation: &Annotation) {
+        self.out.push('@');
+        self.out.push_str(&annotation.name.0);
+        if !annotation.args.is_empty() {
+            self.out.push('(');
+            self.list(&annotation.args);
+            self.out.push(')');
+        }
+    }
+
+    fn typed_ident(&mut self, ident: &OptionallyTypedIdent) {
+        self.out.push_str(&ident.ident.0);
+        if let Some(_type) = &ident._type {
+            self.out.push_str(&format!(": {}", _type));
+        }
+    }
+}
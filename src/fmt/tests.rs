@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        codes::ErrorCode,
+        fmt::format_source,
+        parser::{parse_source, SyntaxError},
+    };
+
+    /// Test for checking if messy source code gets formatted
+    /// canonically and if formatting does not change its meaning
+    #[test]
+    fn test_format() {
+        let unformatted = read_test("unformatted");
+        let formatted = read_test("formatted");
+        assert_eq!(formatted, format_source(&unformatted).unwrap());
+        // Formatting is idempotent
+        assert_eq!(formatted, format_source(&formatted).unwrap());
+        assert_eq!(
+            parse_source(&unformatted).unwrap(),
+            parse_source(&formatted).unwrap()
+        );
+    }
+
+    /// Test for checking if syntax errors are reported instead of panicking
+    #[test]
+    fn test_format_error() {
+        assert_eq!(
+            Err(SyntaxError::new(
+                ErrorCode::UnexpectedToken,
+                "Expected an identifier, received: =".into()
+            )),
+            format_source("var = 5")
+        );
+    }
+
+    fn read_test(test: &str) -> String {
+        fs::read_to_string(format!("tests/fmt/{}.nx", test)).expect("Failed to find file")
+    }
+}
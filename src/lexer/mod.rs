@@ -1,15 +1,42 @@
 mod tests;
 pub mod tokens;
 
+use std::ops::Range;
+
 use clutils::{errors::FileHandlerError, files::FileHandler};
 use tokens::*;
 
+use crate::{
+    codes::ErrorCode,
+    parser::{raise, SyntaxError},
+    util::decimal::Decimal,
+};
+
+/// Token together with the source text around it, see [`Lexer::lex_lossless`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LosslessToken {
+    pub token: Token,
+    /// Whitespace and comments in front of the token
+    /// that are not trailing trivia of the token before
+    pub leading: String,
+    /// Source text of the token itself
+    pub text: String,
+    /// Whitespace and comments after the token up to the end of its line.
+    /// Line breaks are tokens, so this only contains one if a comment ends with it
+    pub trailing: String,
+}
 
 pub struct Lexer {
     filehandler: FileHandler,
     cur_char: Option<char>,
     cur_pos: usize,
     next_pos: usize,
+    /// Line of the current char, starting at 1
+    line: usize,
+    /// Line of the last token that was returned
+    tok_line: usize,
+    /// Position of the first char of the last token that was returned
+    tok_start: usize,
 }
 
 impl Lexer {
@@ -20,126 +47,276 @@ impl Lexer {
             cur_char: None,
             cur_pos: 0,
             next_pos: 0,
+            line: 1,
+            tok_line: 1,
+            tok_start: 0,
         };
         lexer.next_char();
         Ok(lexer)
     }
 
-    pub fn tokenize(&mut self) -> Option<Token> {
+    /// Lexes source code that does not come from a file,
+    /// e.g. a line of the repl
+    pub fn from_source(source: &str) -> Self {
+        let mut lexer = Self {
+            filehandler: FileHandler {
+                file_name: "<source>".into(),
+                file_path: String::new(),
+                full_path: "<source>".into(),
+                content: source.into(),
+            },
+            cur_char: None,
+            cur_pos: 0,
+            next_pos: 0,
+            line: 1,
+            tok_line: 1,
+            tok_start: 0,
+        };
+        lexer.next_char();
+        lexer
+    }
+
+    /// All tokens of the source code, ending with `Token::Eof`
+    pub fn tokens(source: &str) -> Result<Vec<Token>, SyntaxError> {
+        let mut lexer = Self::from_source(source);
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.tokenize()? {
+                Some(Token::Eof) => break,
+                Some(tok) => tokens.push(tok),
+                None => (),
+            }
+        }
+        tokens.push(Token::Eof);
+        Ok(tokens)
+    }
+
+    /// All tokens of the source code, ending with `Token::Eof`, together with
+    /// the whitespace and comments around them. Joining the leading trivia,
+    /// text and trailing trivia of every token results in the source code again
+    pub fn lex_lossless(source: &str) -> Result<Vec<LosslessToken>, SyntaxError> {
+        let chars: Vec<char> = source.chars().collect();
+        let mut lexer = Self::from_source(source);
+        let mut tokens: Vec<LosslessToken> = Vec::new();
+        // End of the last token
+        let mut end = 0;
+        loop {
+            // Comments are left in the gap between two tokens
+            let Some(token) = lexer.tokenize()? else {
+                continue;
+            };
+            // Comments at the end of the source move the position past the end
+            let range = lexer.token_range();
+            let range = range.start.min(chars.len())..range.end.min(chars.len());
+            let mut gap = &chars[end..range.start];
+            if let Some(prev) = tokens.last_mut().filter(|prev| prev.token != Token::Eol) {
+                let split = gap
+                    .iter()
+                    .position(|ch| *ch == '\n')
+                    .map_or(gap.len(), |pos| pos + 1);
+                prev.trailing = gap[..split].iter().collect();
+                gap = &gap[split..];
+            }
+            end = range.end;
+            let is_eof = token == Token::Eof;
+            tokens.push(LosslessToken {
+                token,
+                leading: gap.iter().collect(),
+                text: chars[range].iter().collect(),
+                trailing: String::new(),
+            });
+            if is_eof {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    /// Line (starting at 1) where the last token returned by `tokenize` starts
+    pub fn token_line(&self) -> usize {
+        self.tok_line
+    }
+
+    /// Positions (counted in chars) of the last token returned by `tokenize`.
+    /// If `tokenize` returned `None`, this is the comment that was skipped
+    pub fn token_range(&self) -> Range<usize> {
+        self.tok_start..self.cur_pos
+    }
+
+    /// Next token, `None` if a comment was skipped instead
+    pub fn tokenize(&mut self) -> Result<Option<Token>, SyntaxError> {
         self.skip_whitespace();
-        Some(match self.cur_char {
+        self.tok_line = self.line;
+        self.tok_start = self.cur_pos;
+        Ok(Some(match self.cur_char {
             Some(ch) => match ch {
                 '\n' => {
                     self.next_char();
                     Token::Eol
                 }
-                c if c.is_numeric() => self.tokenize_num(),
+                c if c.is_numeric() => self.tokenize_num()?,
                 c if c.is_alphabetic() || c == '_' => self.tokenize_ident(),
-                _ => return self.tokenize_symbol(),
+                _ => return self.tokenize_symbol(ch),
             },
             None => Token::Eof,
-        })
+        }))
     }
 
-    fn tokenize_num(&mut self) -> Token {
+    fn tokenize_num(&mut self) -> Result<Token, SyntaxError> {
         // TODO: floating points
         let first_pos = self.cur_pos;
         let mut found_fp = false;
         while let Some(ch) = self.cur_char {
             if ch.is_numeric() || ch == '_' {
                 self.next_char();
-            } else if !found_fp && ch == '.' {
+            } else if !found_fp && ch == '.' && self.peek_char().is_some_and(|ch| ch.is_numeric()) {
                 found_fp = true;
                 self.next_char();
             } else {
                 break;
             }
         }
-        let string: String = self.filehandler.content[first_pos..self.cur_pos].into();
+        let string = self.slice_content(first_pos, self.cur_pos);
         // Remove all underscores to ensure that parsing works
         let string: String = string.chars().filter(|&c| c != '_').collect();
-        Token::Literal(Literal::Num(
-            string
-                .parse()
-                .expect(&format!("Failed to parse string: {} to an integer", string)),
-        ))
+        // Decimal literals are suffixed with a `d` like `1.50d`
+        if self.cur_char == Some('d')
+            && !self
+                .peek_char()
+                .is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+        {
+            self.next_char();
+            return match Decimal::parse(&string) {
+                Some(decimal) => Ok(Token::Literal(Literal::Decimal(decimal))),
+                None => raise!(
+                    ErrorCode::InvalidNumber,
+                    "Failed to parse string: {} to a decimal",
+                    string
+                ),
+            };
+        }
+        match string.parse() {
+            Ok(num) => Ok(Token::Literal(Literal::Num(num))),
+            Err(_) => raise!(
+                ErrorCode::InvalidNumber,
+                "Failed to parse string: {} to an integer",
+                string
+            ),
+        }
     }
 
-    fn tokenize_symbol(&mut self) -> Option<Token> {
-        let ret = match self.cur_char {
-            Some(ch) => match ch {
-                '=' => match self.filehandler.content.chars().nth(self.next_pos) {
-                    Some('=') => {
-                        self.next_char();
-                        Token::Operator(Operator::Equals)
-                    }
-                    Some('>') => {
-                        self.next_char();
-                        Token::Arrow
-                    }
-                    _ => Token::Assign,
-                },
-                '+' => Token::Operator(Operator::Plus),
-                '-' => Token::Operator(Operator::Minus),
-                '!' => Token::ExclamMark,
-                '*' => Token::Operator(Operator::Asterisk),
-                '/' => Token::Operator(Operator::Slash),
-                '>' => match self.filehandler.content.chars().nth(self.next_pos) {
-                    Some('=') => {
-                        self.next_char();
-                        Token::Operator(Operator::GreaterEquals)
-                    }
-                    _ => Token::Operator(Operator::Greater),
-                },
-                '<' => match self.filehandler.content.chars().nth(self.next_pos) {
-                    Some('=') => {
-                        self.next_char();
-                        Token::Operator(Operator::LesserEquals)
-                    }
-                    _ => Token::Operator(Operator::Lesser),
-                },
-                ';' => Token::Eol,
-                '(' => Token::LParent,
-                ')' => Token::RParent,
-                '{' => Token::LCurly,
-                '}' => Token::RCurly,
-                '"' => self.tokenize_string(),
-                ':' => match self.filehandler.content.chars().nth(self.next_pos) {
-                    Some(':') => {
-                        self.next_char();
-                        Token::ConstAssign
-                    }
-                    Some('=') => {
-                        self.next_char();
-                        Token::VarAssign
-                    }
-                    _ => Token::Colon,
-                },
-                ',' => Token::Comma,
-                '.' => Token::Dot,
-                '#' => return self.tokenize_comment(),
-                _ => panic!("Invalid symbol: {:?}", &self.cur_char),
+    fn tokenize_symbol(&mut self, ch: char) -> Result<Option<Token>, SyntaxError> {
+        let ret = match ch {
+            '=' => match self.filehandler.content.chars().nth(self.next_pos) {
+                Some('=') => {
+                    self.next_char();
+                    Token::Operator(Operator::Equals)
+                }
+                Some('>') => {
+                    self.next_char();
+                    Token::Arrow
+                }
+                _ => Token::Assign,
+            },
+            '+' => Token::Operator(Operator::Plus),
+            '-' => Token::Operator(Operator::Minus),
+            '!' => match self.peek_char() {
+                Some('=') => {
+                    self.next_char();
+                    Token::Operator(Operator::NotEquals)
+                }
+                _ => Token::ExclamMark,
+            },
+            '*' => Token::Operator(Operator::Asterisk),
+            '/' if self.peek_char() == Some('/')
+                && self.filehandler.content.chars().nth(self.next_pos + 1) == Some('/') =>
+            {
+                return Ok(Some(self.tokenize_doc_comment()))
+            }
+            '/' => Token::Operator(Operator::Slash),
+            '>' => match self.filehandler.content.chars().nth(self.next_pos) {
+                Some('=') => {
+                    self.next_char();
+                    Token::Operator(Operator::GreaterEquals)
+                }
+                _ => Token::Operator(Operator::Greater),
+            },
+            '<' => match self.filehandler.content.chars().nth(self.next_pos) {
+                Some('=') => {
+                    self.next_char();
+                    Token::Operator(Operator::LesserEquals)
+                }
+                _ => Token::Operator(Operator::Lesser),
             },
-            None => todo!(),
+            ';' => Token::Eol,
+            '(' => Token::LParent,
+            ')' => Token::RParent,
+            '[' => Token::LSquare,
+            ']' => Token::RSquare,
+            '{' => Token::LCurly,
+            '}' => Token::RCurly,
+            '"' => self.tokenize_string()?,
+            ':' => match self.filehandler.content.chars().nth(self.next_pos) {
+                Some(':') => {
+                    self.next_char();
+                    Token::ConstAssign
+                }
+                Some('=') => {
+                    self.next_char();
+                    Token::VarAssign
+                }
+                _ => Token::Colon,
+            },
+            ',' => Token::Comma,
+            '@' => Token::At,
+            '?' => match self.peek_char() {
+                Some('?') => {
+                    self.next_char();
+                    Token::Operator(Operator::NullCoalesce)
+                }
+                Some('.') => {
+                    self.next_char();
+                    Token::QuestionDot
+                }
+                Some('[') => {
+                    self.next_char();
+                    Token::QuestionSquare
+                }
+                _ => Token::QuestionMark,
+            },
+            '.' => match self.peek_char() {
+                Some('.') => {
+                    self.next_char();
+                    Token::DotDot
+                }
+                _ => Token::Dot,
+            },
+            '#' => return Ok(self.tokenize_comment()),
+            _ => raise!(
+                ErrorCode::InvalidCharacter,
+                "Invalid symbol: {:?}",
+                &self.cur_char
+            ),
         };
         self.next_char();
-        Some(ret)
+        Ok(Some(ret))
     }
 
-    fn tokenize_string(&mut self) -> Token {
+    fn tokenize_string(&mut self) -> Result<Token, SyntaxError> {
         self.next_char();
         let begin_pos = self.cur_pos;
-        while self.filehandler.content.chars().nth(self.cur_pos) != Some('"') {
-            if self.filehandler.content.chars().nth(self.cur_pos) == Some('{') {
+        while self.cur_char != Some('"') {
+            if self.cur_char == Some('{') {
                 self.next_char();
-                while self.filehandler.content.chars().nth(self.cur_pos) != Some('}') {
+                while self.cur_char != Some('}') {
+                    self.expect_not_eof("Unterminated interpolation in string literal")?;
                     self.next_char();
                 }
             }
+            self.expect_not_eof("Unterminated string literal")?;
             self.next_char();
         }
-        let string = &self.filehandler.content[begin_pos..self.cur_pos];
-        Token::Literal(Literal::Str(string.into()))
+        let string = self.slice_content(begin_pos, self.cur_pos);
+        Ok(Token::Literal(Literal::Str(string)))
     }
 
     fn tokenize_comment(&mut self) -> Option<Token> {
@@ -155,6 +332,23 @@ impl Lexer {
         None
     }
 
+    /// Doc comments are kept, unlike other comments, so they can be
+    /// attached to definitions. The text excludes the line break
+    /// and a single space after the slashes
+    fn tokenize_doc_comment(&mut self) -> Token {
+        for _ in 0..3 {
+            self.next_char();
+        }
+        if self.cur_char == Some(' ') {
+            self.next_char();
+        }
+        let begin_pos = self.cur_pos;
+        while self.cur_char.is_some_and(|ch| ch != '\n') {
+            self.next_char();
+        }
+        Token::DocComment(self.slice_content(begin_pos, self.cur_pos))
+    }
+
     fn tokenize_ident(&mut self) -> Token {
         let first_pos = self.cur_pos;
         while let Some(ch) = self.cur_char {
@@ -164,8 +358,8 @@ impl Lexer {
                 break;
             }
         }
-        let ident: String = self.filehandler.content[first_pos..self.cur_pos].into();
-        return match ident.as_str() {
+        let ident = self.slice_content(first_pos, self.cur_pos);
+        match ident.as_str() {
             "var" => Token::Var,
             "const" => Token::Const,
             "func" => Token::Func,
@@ -174,6 +368,7 @@ impl Lexer {
             "use" => Token::Use,
 
             "loop" => Token::Loop,
+            "in" => Token::In,
             "if" => Token::If,
             "else" => Token::Else,
             "when" => Token::When,
@@ -187,29 +382,49 @@ impl Lexer {
 
             "true" => Token::Literal(Literal::Bool(true)),
             "false" => Token::Literal(Literal::Bool(false)),
+            "none" => Token::None,
 
-            _ => Token::Ident(self.filehandler.content[first_pos..self.cur_pos].into()),
-        };
+            _ => Token::Ident(ident),
+        }
     }
 
     fn next_char(&mut self) {
+        if self.cur_char == Some('\n') {
+            self.line += 1;
+        }
         self.cur_pos = self.next_pos;
         self.cur_char = self.filehandler.content.chars().nth(self.cur_pos);
         self.next_pos += 1;
     }
 
+    /// Positions of the lexer are counted in chars,
+    /// so the content cannot be sliced by bytes
+    fn slice_content(&self, start: usize, end: usize) -> String {
+        self.filehandler
+            .content
+            .chars()
+            .skip(start)
+            .take(end - start)
+            .collect()
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.filehandler.content.chars().nth(self.next_pos)
+    }
+
+    fn expect_not_eof(&self, msg: &str) -> Result<(), SyntaxError> {
+        if self.cur_char.is_none() {
+            raise!(ErrorCode::UnterminatedString, "{}", msg)
+        }
+        Ok(())
+    }
+
     fn skip_whitespace(&mut self) {
-        match self.cur_char {
-            Some(mut ch) => {
-                while ch.is_whitespace() && ch != '\n' {
-                    self.next_char();
-                    match self.cur_char {
-                        Some(cch) => ch = cch,
-                        None => (),
-                    }
-                }
+        while let Some(ch) = self.cur_char {
+            if !ch.is_whitespace() || ch == '\n' {
+                break;
             }
-            None => (),
+            self.next_char();
         }
     }
 }
@@ -1,14 +1,17 @@
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use crate::{
+        codes::ErrorCode,
         lexer::{Lexer, Literal, Operator, Token},
-        util,
+        util::{self, decimal::Decimal},
     };
 
     #[test]
     fn test_string() {
         let mut lexer = get_lexer("test");
-        lexer.tokenize();
+        lexer.tokenize().unwrap();
     }
 
     /// Test for checking if literals like numbers, booleans
@@ -25,16 +28,18 @@ mod tests {
             // Integers
             Token::Literal(Literal::Num(9875986234.0)),
             // Integers with visual seperator
-            Token::Literal(Literal::Num(1254_890.0)),
+            Token::Literal(Literal::Num(1_254_890.0)),
             // Floats
             Token::Literal(Literal::Num(5643877689.9886)),
             // Booleans
             Token::Literal(Literal::Bool(true)),
             Token::Literal(Literal::Bool(false)),
+            // Decimals
+            Token::Literal(Literal::Decimal(Decimal::new(150, 2))),
         ];
         for expect in expected {
-            let tok = lexer.tokenize();
-            lexer.tokenize();
+            let tok = lexer.tokenize().unwrap();
+            lexer.tokenize().unwrap();
             if let Some(tok) = tok {
                 assert_eq!(expect, tok);
             }
@@ -61,8 +66,8 @@ mod tests {
             Token::Ident(String::from("iff")),
         ];
         for expect in expected {
-            let tok = util::get_next_tok(&mut lexer);
-            lexer.tokenize();
+            let tok = util::get_next_tok(&mut lexer).unwrap();
+            lexer.tokenize().unwrap();
             assert_eq!(expect, tok)
         }
     }
@@ -81,10 +86,12 @@ mod tests {
             Token::Operator(Operator::Equals),
             Token::Operator(Operator::GreaterEquals),
             Token::Arrow,
+            Token::DotDot,
+            Token::At,
         ];
         for expect in expected {
-            let tok = util::get_next_tok(&mut lexer);
-            lexer.tokenize();
+            let tok = util::get_next_tok(&mut lexer).unwrap();
+            lexer.tokenize().unwrap();
             assert_eq!(expect, tok)
         }
     }
@@ -94,12 +101,104 @@ mod tests {
     #[test]
     fn test_comments() {
         let mut lexer = get_lexer("comments");
-        let tok = util::get_next_tok(&mut lexer);
+        let tok = util::get_next_tok(&mut lexer).unwrap();
         assert_eq!(Token::Var, tok);
-        let next_tok = util::get_next_tok(&mut lexer);
+        let next_tok = util::get_next_tok(&mut lexer).unwrap();
         assert_eq!(Token::Eof, next_tok);
     }
 
+    /// Test for checking if doc comments are kept
+    /// and `/` and `//` are still operators
+    #[test]
+    fn test_doc_comments() {
+        assert_eq!(
+            Ok(vec![
+                Token::DocComment("Doubles n".into()),
+                Token::Eol,
+                Token::DocComment("".into()),
+                Token::Eol,
+                Token::Ident("a".into()),
+                Token::Operator(Operator::Slash),
+                Token::Ident("b".into()),
+                Token::Eof,
+            ]),
+            Lexer::tokens("/// Doubles n\n///\na / b")
+        );
+    }
+
+    /// Test for checking if source code that is not
+    /// in a file gets tokenized like a file
+    #[test]
+    fn test_from_source() {
+        let mut lexer = Lexer::from_source("x != 1.5d");
+        let expected = [
+            Token::Ident("x".into()),
+            Token::Operator(Operator::NotEquals),
+            Token::Literal(Literal::Decimal(Decimal::new(15, 1))),
+            Token::Eof,
+        ];
+        for expect in expected.clone() {
+            assert_eq!(Ok(Some(expect)), lexer.tokenize());
+        }
+        assert_eq!(Ok(expected.to_vec()), Lexer::tokens("x != 1.5d"));
+    }
+
+    /// Test for checking if invalid tokens are returned
+    /// as syntax errors with their code
+    #[test]
+    fn test_invalid_tokens() {
+        let code = |source: &str| Lexer::tokens(source).unwrap_err().code();
+        assert_eq!(ErrorCode::InvalidCharacter, code("1 $ 2"));
+        assert_eq!(ErrorCode::UnterminatedString, code("\"abc"));
+        assert_eq!(ErrorCode::UnterminatedString, code("\"a {b"));
+
+        let mut lexer = Lexer::from_source("x\n$");
+        assert_eq!(Ok(Some(Token::Ident("x".into()))), lexer.tokenize());
+        assert_eq!(Ok(Some(Token::Eol)), lexer.tokenize());
+        assert!(lexer.tokenize().is_err());
+        assert_eq!(2, lexer.token_line());
+    }
+
+    /// Test for checking if whitespace and comments are attached
+    /// to the tokens and the source code can be rebuilt from them
+    #[test]
+    fn test_lossless() {
+        let tokens = Lexer::lex_lossless("  var x = 1 # one\n\n# note\nx  ").unwrap();
+        let texts: Vec<(&str, &str, &str)> = tokens
+            .iter()
+            .map(|tok| {
+                (
+                    tok.leading.as_str(),
+                    tok.text.as_str(),
+                    tok.trailing.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("  ", "var", " "),
+                ("", "x", " "),
+                ("", "=", " "),
+                ("", "1", " # one\n"),
+                ("", "\n", ""),
+                ("# note\n", "x", "  "),
+                ("", "", ""),
+            ],
+            texts
+        );
+        assert_eq!(Token::Eof, tokens.last().unwrap().token);
+
+        for file in fs::read_dir("tests/lexer").unwrap() {
+            let source = fs::read_to_string(file.unwrap().path()).unwrap();
+            let rebuilt: String = Lexer::lex_lossless(&source)
+                .unwrap()
+                .into_iter()
+                .map(|tok| tok.leading + &tok.text + &tok.trailing)
+                .collect();
+            assert_eq!(source, rebuilt);
+        }
+    }
+
     fn get_lexer(test: &str) -> Lexer {
         Lexer::new(&format!("tests/lexer/{}.nx", test)).expect("Failed to open file")
     }
@@ -1,4 +1,6 @@
-use std::{fmt::Display, collections::HashMap, ops::Range};
+use std::{collections::HashMap, fmt::Display, ops::Range};
+
+use crate::util::decimal::Decimal;
 
 pub type StringRef = (Vec<char>, Option<HashMap<Range<usize>, Vec<Token>>>);
 
@@ -12,6 +14,7 @@ pub enum Token {
     Const,
 
     Loop,
+    In,
     If,
     Else,
     When,
@@ -25,15 +28,23 @@ pub enum Token {
     Local,
 
     Dot,
+    DotDot,
     Comma,
     Colon,
     QuestionMark,
+    /// `?.` for optional field access
+    QuestionDot,
+    /// `?[` for optional indexing
+    QuestionSquare,
     ExclamMark,
+    /// `@` in front of the name of an annotation
+    At,
     Arrow,
     Assign,
 
     Literal(Literal),
     Ident(String),
+    None,
 
     LParent,
     RParent,
@@ -45,6 +56,9 @@ pub enum Token {
     ConstAssign,
     VarAssign,
 
+    /// `/// text`, contains the text without the slashes
+    DocComment(String),
+
     Eol,
     Eof,
 }
@@ -53,6 +67,7 @@ pub enum Token {
 pub enum Literal {
     Str(String),
     Num(f64),
+    Decimal(Decimal),
     Bool(bool),
 }
 
@@ -68,6 +83,7 @@ pub enum Operator {
     Minus,
     Asterisk,
     Slash,
+    NullCoalesce,
 }
 
 impl Display for Literal {
@@ -78,6 +94,7 @@ impl Display for Literal {
             match self {
                 Literal::Str(str) => str.to_string(),
                 Literal::Num(num) => num.to_string(),
+                Literal::Decimal(dec) => format!("{}d", dec),
                 Literal::Bool(bool) => bool.to_string(),
             }
         )
@@ -100,6 +117,7 @@ impl Display for Operator {
                 Operator::Minus => "-",
                 Operator::Asterisk => "*",
                 Operator::Slash => "/",
+                Operator::NullCoalesce => "??",
             }
         )
     }
@@ -113,6 +131,7 @@ impl Display for Token {
             Token::Struct => "struct".into(),
             Token::Func => "func".into(),
             Token::Loop => "loop".into(),
+            Token::In => "in".into(),
             Token::If => "if".into(),
             Token::When => "when".into(),
             Token::Var => "var".into(),
@@ -124,10 +143,14 @@ impl Display for Token {
             Token::Local => "local".into(),
             Token::Literal(lit) => lit.to_string(),
             Token::Dot => ".".into(),
+            Token::DotDot => "..".into(),
             Token::Comma => ",".into(),
             Token::Colon => ":".into(),
             Token::QuestionMark => "?".into(),
+            Token::QuestionDot => "?.".into(),
+            Token::QuestionSquare => "?[".into(),
             Token::ExclamMark => "!".into(),
+            Token::At => "@".into(),
             Token::Eol => "Eol".into(),
             Token::Eof => "Eof".into(),
             Token::Operator(op) => op.to_string(),
@@ -141,8 +164,10 @@ impl Display for Token {
             Token::Assign => "=".into(),
             Token::Else => "else".into(),
             Token::Ident(ident) => ident.into(),
+            Token::None => "none".into(),
             Token::ConstAssign => "::".into(),
             Token::VarAssign => ":=".into(),
+            Token::DocComment(doc) => format!("/// {}", doc),
         };
         write!(f, "{}", lit)
     }
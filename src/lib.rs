@@ -1,4 +1,16 @@
+#![allow(clippy::module_inception)]
+
+pub mod codes;
+pub mod debugger;
+pub mod docs;
+pub mod evaluator;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fmt;
 pub mod lexer;
+pub mod lsp;
 pub mod parser;
-pub mod evaluator;
-pub mod util;
\ No newline at end of file
+pub mod testing;
+pub mod util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,305 @@
+//! Analysis of source code for editors, used by the `nexus-lsp` binary.
+//!
+//! Every function takes the whole source code and parses it again.
+//! Nodes only know the line they start on, so positions are lines
+//! (starting at 1) and columns (in chars, starting at 0), and the
+//! symbol table only knows in which line something was declared.
+//! The parser stops at the first syntax error, so there is at most
+//! one diagnostic and no other analysis works until it is fixed.
+//!
+//! The server itself is behind the `lsp` feature:
+//! `cargo run --features lsp --bin nexus-lsp`
+
+mod semantic_tokens;
+mod tests;
+
+pub use semantic_tokens::{semantic_tokens, SemanticKind, SemanticToken};
+
+use crate::{
+    codes::ErrorCode,
+    evaluator::{builtins::BUILTIN_NAMES, Evaluator},
+    parser::{
+        ast::{BlockStmt, Expression, OptionallyTypedIdent, Program, Statement, VarStmt},
+        parse_source, parse_source_located,
+        visit::{self, Visitor},
+    },
+};
+
+pub const KEYWORDS: [&str; 17] = [
+    "var", "const", "func", "struct", "enum", "use", "loop", "in", "if", "else", "when", "and",
+    "or", "return", "break", "local", "none",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Variable,
+    Constant,
+    Function,
+    /// Argument of a function, declared on the line of the function
+    Argument,
+}
+
+/// A declaration of the source code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+    /// Declaration as it is shown on hover, e.g. `const max: Num`
+    pub detail: String,
+    pub docs: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompletionKind {
+    Variable,
+    Function,
+    Builtin,
+    Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: Option<String>,
+}
+
+/// Syntax errors of the source code
+pub fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    match parse_source_located(source) {
+        Ok(_) => Vec::new(),
+        Err((err, line)) => vec![Diagnostic {
+            line,
+            code: err.code(),
+            message: err.msg,
+        }],
+    }
+}
+
+/// Every declaration of the program, including the ones inside of blocks
+pub fn symbols(program: &Program) -> Vec<Symbol> {
+    let mut collector = SymbolCollector {
+        symbols: Vec::new(),
+        line: 1,
+    };
+    collector.visit_program(program);
+    collector.symbols
+}
+
+/// Declaration of the identifier at the position.
+/// If it was declared multiple times, the last declaration
+/// before the position wins, otherwise the first one after it
+pub fn definition(source: &str, line: usize, col: usize) -> Option<Symbol> {
+    let name = word_at(source, line, col)?;
+    let symbols = symbols(&parse_source(source).ok()?);
+    let (before, after): (Vec<_>, Vec<_>) = symbols
+        .into_iter()
+        .filter(|symbol| symbol.name == name)
+        .partition(|symbol| symbol.line <= line);
+    before.into_iter().last().or(after.into_iter().next())
+}
+
+/// Description of the identifier or literal at the position
+pub fn hover(source: &str, line: usize, col: usize) -> Option<String> {
+    let word = word_at(source, line, col)?;
+    if let Some(_type) = literal_type(&word) {
+        return Some(_type.into());
+    }
+    if let Some(symbol) = definition(source, line, col) {
+        return Some(match symbol.docs {
+            Some(docs) => format!("{}\n\n{}", symbol.detail, docs),
+            None => symbol.detail,
+        });
+    }
+    BUILTIN_NAMES
+        .contains(&word.as_str())
+        .then(|| format!("builtin function {}", word))
+}
+
+/// Identifiers declared before the line, builtins and keywords.
+/// If the program cannot be parsed, only builtins and keywords are returned
+pub fn completions(source: &str, line: usize) -> Vec<Completion> {
+    let mut completions: Vec<Completion> = Vec::new();
+    let symbols = parse_source(source)
+        .map(|program| symbols(&program))
+        .unwrap_or_default();
+    for symbol in symbols.into_iter().filter(|symbol| symbol.line <= line) {
+        let completion = Completion {
+            label: symbol.name,
+            kind: match symbol.kind {
+                SymbolKind::Function => CompletionKind::Function,
+                _ => CompletionKind::Variable,
+            },
+            detail: Some(symbol.detail),
+        };
+        // Later declarations shadow earlier ones
+        match completions
+            .iter_mut()
+            .find(|other| other.label == completion.label)
+        {
+            Some(other) => *other = completion,
+            None => completions.push(completion),
+        }
+    }
+    for builtin in BUILTIN_NAMES {
+        completions.push(Completion {
+            label: builtin.into(),
+            kind: CompletionKind::Builtin,
+            detail: None,
+        });
+    }
+    for keyword in KEYWORDS {
+        completions.push(Completion {
+            label: keyword.into(),
+            kind: CompletionKind::Keyword,
+            detail: None,
+        });
+    }
+    completions
+}
+
+/// The identifier or number that contains the column of the line
+pub fn word_at(source: &str, line: usize, col: usize) -> Option<String> {
+    let chars: Vec<char> = source.lines().nth(line.checked_sub(1)?)?.chars().collect();
+    let is_ident = |ch: &char| ch.is_alphanumeric() || *ch == '_';
+    let word = span(&chars, col, |ch| is_ident(ch) || *ch == '.')?;
+    // Dots only belong to the word if it is a number
+    match word.starts_with(|ch: char| ch.is_numeric()) {
+        true => Some(word),
+        false => span(&chars, col, is_ident),
+    }
+}
+
+/// The longest run of chars around the column that match the predicate
+fn span(chars: &[char], col: usize, pred: impl Fn(&char) -> bool) -> Option<String> {
+    if !chars.get(col).is_some_and(&pred) {
+        return None;
+    }
+    let start = chars[..col]
+        .iter()
+        .rposition(|ch| !pred(ch))
+        .map_or(0, |pos| pos + 1);
+    let end = chars[col..]
+        .iter()
+        .position(|ch| !pred(ch))
+        .map_or(chars.len(), |pos| col + pos);
+    Some(chars[start..end].iter().collect())
+}
+
+fn literal_type(word: &str) -> Option<&'static str> {
+    match word {
+        "true" | "false" => Some("Bool"),
+        "none" => Some("None"),
+        _ if !word.starts_with(|ch: char| ch.is_numeric()) => None,
+        _ if word.ends_with('d') => Some("Decimal"),
+        _ => Some("Num"),
+    }
+}
+
+struct SymbolCollector {
+    symbols: Vec<Symbol>,
+    /// Line of the statement that is currently visited
+    line: usize,
+}
+
+impl SymbolCollector {
+    fn visit_lines(&mut self, stmts: &[Statement], lines: &[usize]) {
+        for (i, stmt) in stmts.iter().enumerate() {
+            let prev = self.line;
+            self.line = lines.get(i).copied().unwrap_or(prev);
+            self.visit_stmt(stmt);
+            self.line = prev;
+        }
+    }
+
+    fn declare(&mut self, var: &VarStmt) {
+        let (kind, detail) = match &var.val {
+            Expression::Func(func) => (
+                SymbolKind::Function,
+                format!(
+                    "func {}({}){}",
+                    var.name.ident,
+                    func.args
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    func.ret_type
+                        .as_ref()
+                        .map(|ret_type| format!(": {}", ret_type))
+                        .unwrap_or_default()
+                ),
+            ),
+            val => {
+                let keyword = if var.is_const { "const" } else { "var" };
+                let _type = var
+                    .name
+                    ._type
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .or_else(|| Evaluator::new().static_type(val).map(Into::into));
+                (
+                    match var.is_const {
+                        true => SymbolKind::Constant,
+                        false => SymbolKind::Variable,
+                    },
+                    match _type {
+                        Some(_type) => format!("{} {}: {}", keyword, var.name.ident, _type),
+                        None => format!("{} {}", keyword, var.name.ident),
+                    },
+                )
+            }
+        };
+        self.symbols.push(Symbol {
+            name: var.name.ident.to_string(),
+            kind,
+            line: self.line,
+            detail,
+            docs: var.docs.clone(),
+        });
+    }
+
+    fn declare_arg(&mut self, arg: &OptionallyTypedIdent) {
+        self.symbols.push(Symbol {
+            name: arg.ident.to_string(),
+            kind: SymbolKind::Argument,
+            line: self.line,
+            detail: arg.to_string(),
+            docs: None,
+        });
+    }
+}
+
+impl Visitor for SymbolCollector {
+    fn visit_program(&mut self, program: &Program) {
+        self.visit_lines(&program.stmts, &program.lines);
+    }
+
+    fn visit_block(&mut self, block: &BlockStmt) {
+        self.visit_lines(&block.stmts, &block.lines);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Statement) {
+        if let Statement::Variable(var) = stmt {
+            self.declare(var);
+        }
+        visit::walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expression) {
+        if let Expression::Func(func) = expr {
+            for arg in &func.args {
+                self.declare_arg(arg);
+            }
+        }
+        visit::walk_expr(self, expr);
+    }
+}
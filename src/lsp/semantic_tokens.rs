@@ -0,0 +1,128 @@
+use std::ops::Range;
+
+use crate::lexer::{
+    tokens::{self, Token},
+    Lexer,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticKind {
+    Keyword,
+    Identifier,
+    /// Identifiers that are called or declared as functions
+    Function,
+    Number,
+    String,
+    Comment,
+}
+
+impl SemanticKind {
+    /// Every kind, in the order of their index in the legend of the language server
+    pub const ALL: [SemanticKind; 6] = [
+        SemanticKind::Keyword,
+        SemanticKind::Identifier,
+        SemanticKind::Function,
+        SemanticKind::Number,
+        SemanticKind::String,
+        SemanticKind::Comment,
+    ];
+
+    /// Name of the token type in the language server protocol
+    pub fn lsp_name(&self) -> &'static str {
+        match self {
+            SemanticKind::Keyword => "keyword",
+            SemanticKind::Identifier => "variable",
+            SemanticKind::Function => "function",
+            SemanticKind::Number => "number",
+            SemanticKind::String => "string",
+            SemanticKind::Comment => "comment",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticToken {
+    pub kind: SemanticKind,
+    /// Positions in the source code, counted in chars
+    pub range: Range<usize>,
+    /// Line where the token starts, starting at 1
+    pub line: usize,
+    /// Column where the token starts, counted in chars starting at 0
+    pub col: usize,
+}
+
+/// Classifies the tokens of the source code for syntax highlighting.
+/// Operators and punctuation are left out. If the lexer fails,
+/// the tokens up to the error are returned
+pub fn semantic_tokens(source: &str) -> Vec<SemanticToken> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut lexed: Vec<(Option<Token>, Range<usize>, usize)> = Vec::new();
+    let mut lexer = Lexer::from_source(source);
+    loop {
+        match lexer.tokenize() {
+            Ok(Some(Token::Eof)) | Err(_) => break,
+            Ok(tok) => lexed.push((tok, lexer.token_range(), lexer.token_line())),
+        }
+    }
+
+    let mut tokens = Vec::new();
+    for (i, (tok, range, line)) in lexed.iter().enumerate() {
+        let next = |offset: usize| lexed.get(i + offset).and_then(|(tok, ..)| tok.as_ref());
+        let kind = match tok {
+            None | Some(Token::DocComment(_)) => SemanticKind::Comment,
+            Some(Token::Ident(_)) => match (next(1), next(2)) {
+                (Some(Token::LParent), _) | (Some(Token::ConstAssign), Some(Token::Func)) => {
+                    SemanticKind::Function
+                }
+                _ => SemanticKind::Identifier,
+            },
+            Some(Token::Literal(tokens::Literal::Str(_))) => SemanticKind::String,
+            Some(Token::Literal(tokens::Literal::Num(_) | tokens::Literal::Decimal(_))) => {
+                SemanticKind::Number
+            }
+            Some(tok) if is_keyword(tok) => SemanticKind::Keyword,
+            Some(_) => continue,
+        };
+        // Comments include the line break that ends them
+        let mut range = range.clone();
+        while range.end > range.start && chars.get(range.end - 1) == Some(&'\n') {
+            range.end -= 1;
+        }
+        let col = chars[..range.start]
+            .iter()
+            .rev()
+            .take_while(|ch| **ch != '\n')
+            .count();
+        tokens.push(SemanticToken {
+            kind,
+            range,
+            line: *line,
+            col,
+        });
+    }
+    tokens
+}
+
+fn is_keyword(tok: &Token) -> bool {
+    matches!(
+        tok,
+        Token::Use
+            | Token::Enum
+            | Token::Struct
+            | Token::Func
+            | Token::Var
+            | Token::Const
+            | Token::Loop
+            | Token::In
+            | Token::If
+            | Token::Else
+            | Token::When
+            | Token::And
+            | Token::Or
+            | Token::Break
+            | Token::Return
+            | Token::Local
+            | Token::None
+            | Token::Literal(tokens::Literal::Bool(_))
+    )
+}
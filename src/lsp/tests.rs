@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        codes::ErrorCode,
+        lsp::{
+            completions, definition, diagnostics, hover, semantic_tokens, word_at, CompletionKind,
+            Diagnostic, SemanticKind, SymbolKind,
+        },
+    };
+
+    /// Test for checking if syntax errors are reported with their line
+    #[test]
+    fn test_diagnostics() {
+        assert!(diagnostics(&read_test("lsp")).is_empty());
+        assert_eq!(
+            vec![Diagnostic {
+                line: 2,
+                code: ErrorCode::UnexpectedToken,
+                message: "Expected an identifier, received: =".into(),
+            }],
+            diagnostics("var x = 1\nvar = 5")
+        );
+    }
+
+    /// Test for checking if identifiers resolve to the
+    /// closest declaration before them
+    #[test]
+    fn test_definition() {
+        let source = read_test("lsp");
+        let max = definition(&source, 8, 16).unwrap();
+        assert_eq!((2, SymbolKind::Constant), (max.line, max.kind));
+        assert_eq!(9, definition(&source, 10, 6).unwrap().line);
+        let factor = definition(&source, 5, 18).unwrap();
+        assert_eq!((4, SymbolKind::Argument), (factor.line, factor.kind));
+        assert_eq!(None, definition(&source, 10, 0));
+    }
+
+    /// Test for checking if hovering shows declarations,
+    /// doc comments and the types of literals
+    #[test]
+    fn test_hover() {
+        let source = read_test("lsp");
+        assert_eq!(
+            Some("const max: Num\n\nLargest allowed size".into()),
+            hover(&source, 8, 16)
+        );
+        assert_eq!(
+            Some("func double(n, factor: Num)".into()),
+            hover(&source, 8, 9)
+        );
+        assert_eq!(Some("Decimal".into()), hover(&source, 8, 22));
+        assert_eq!(Some("var x: Str".into()), hover(&source, 10, 6));
+        assert_eq!(Some("builtin function print".into()), hover(&source, 10, 2));
+        assert_eq!(None, hover(&source, 3, 0));
+    }
+
+    /// Test for checking if only identifiers declared so far are completed
+    #[test]
+    fn test_completions() {
+        let source = read_test("lsp");
+        let completions = completions(&source, 4);
+        let labels: Vec<_> = completions
+            .iter()
+            .filter(|completion| {
+                matches!(
+                    completion.kind,
+                    CompletionKind::Variable | CompletionKind::Function
+                )
+            })
+            .map(|completion| completion.label.as_str())
+            .collect();
+        assert_eq!(vec!["max", "double", "n", "factor"], labels);
+        assert!(completions
+            .iter()
+            .any(|completion| completion.label == "print"
+                && completion.kind == CompletionKind::Builtin));
+    }
+
+    /// Test for checking if tokens are classified with their
+    /// positions, including comments that the parser never sees
+    #[test]
+    fn test_semantic_tokens() {
+        let source = read_test("semantic");
+        let chars: Vec<char> = source.chars().collect();
+        let tokens: Vec<_> = semantic_tokens(&source)
+            .into_iter()
+            .map(|tok| {
+                (
+                    tok.kind,
+                    chars[tok.range].iter().collect::<String>(),
+                    tok.line,
+                    tok.col,
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (SemanticKind::Comment, "# comment".into(), 1, 0),
+                (SemanticKind::Comment, "/// Doubles".into(), 2, 0),
+                (SemanticKind::Function, "double".into(), 3, 0),
+                (SemanticKind::Keyword, "func".into(), 3, 10),
+                (SemanticKind::Identifier, "n".into(), 3, 15),
+                (SemanticKind::Identifier, "n".into(), 3, 20),
+                (SemanticKind::Number, "2.5d".into(), 3, 24),
+                (SemanticKind::Keyword, "var".into(), 4, 0),
+                (SemanticKind::Identifier, "s".into(), 4, 4),
+                (SemanticKind::String, "\"hï\"".into(), 4, 8),
+                (SemanticKind::Comment, "# trailing #".into(), 4, 13),
+                (SemanticKind::Keyword, "if".into(), 5, 0),
+                (SemanticKind::Keyword, "true".into(), 5, 3),
+                (SemanticKind::Function, "double".into(), 5, 10),
+                (SemanticKind::Number, "1".into(), 5, 17),
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn test_word_at() {
+        assert_eq!(Some("bar".into()), word_at("foo.bar(1.5)", 1, 5));
+        assert_eq!(Some("1.5".into()), word_at("foo.bar(1.5)", 1, 9));
+        assert_eq!(None, word_at("foo.bar(1.5)", 1, 7));
+        assert_eq!(None, word_at("foo", 2, 0));
+    }
+
+    fn read_test(test: &str) -> String {
+        fs::read_to_string(format!("tests/lsp/{}.nx", test)).expect("Failed to find file")
+    }
+}
@@ -1,7 +1,37 @@
-
 use std::fmt::Display;
 
+use crate::util::decimal::Decimal;
+
+/// All statements of a file
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Program {
+    pub stmts: Vec<Statement>,
+    /// Line of each statement, starting at 1.
+    /// Empty if the program was not created by the parser
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lines: Vec<usize>,
+}
+
+impl Program {
+    pub fn new(stmts: Vec<Statement>) -> Self {
+        Self {
+            stmts,
+            lines: Vec::new(),
+        }
+    }
+}
+
+/// Lines are ignored, so the same code
+/// at different positions is equal
+impl PartialEq for Program {
+    fn eq(&self, other: &Self) -> bool {
+        self.stmts == other.stmts
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
     Variable(VarStmt),
     Return(ReturnStmt),
@@ -12,29 +42,38 @@ pub enum Statement {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression {
     Ident(Ident),
     Literal(Literal),
     Prefix(PrefixExpr),
     Infix(InfixExpr),
+    Assign(AssignExpr),
+    Ternary(TernaryExpr),
     Index(IndexExpr),
+    Field(FieldExpr),
+    Slice(SliceExpr),
     Call(CallExpr),
     List(ListExpr),
+    Hash(HashExpr),
     None,
 
     If(IfExpr),
     Loop(LoopExpr),
     When(WhenExpr),
     Func(FuncExpr),
+    Block(BlockStmt),
     Annotation(AnnotationExpr),
     Struct(StructExpr),
     Enum(EnumExpr),
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Literal {
     Str(String),
     Num(f64),
+    Decimal(Decimal),
     Bool(bool),
 }
 
@@ -46,6 +85,7 @@ impl Display for Literal {
             match self {
                 Literal::Str(str) => str.to_owned(),
                 Literal::Num(num) => num.to_string(),
+                Literal::Decimal(dec) => dec.to_string(),
                 Literal::Bool(bool) => bool.to_string(),
             }
         )
@@ -53,62 +93,121 @@ impl Display for Literal {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ident(pub String);
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OptionallyTypedIdent {
     pub ident: Ident,
     pub _type: Option<Ident>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VarStmt {
     pub name: OptionallyTypedIdent,
     pub val: Expression,
     pub is_const: bool,
+    /// Text of the `///` comments above the declaration,
+    /// one line per comment
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub docs: Option<String>,
+    /// Annotations above the declaration, in the order they were written
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub annotations: Vec<Annotation>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReturnStmt {
     pub val: Option<Expression>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BreakStmt {
     pub label: Option<Ident>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalStmt {
     pub val: Box<Statement>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UseStmt {
     // TODO: Import paths
     pub import: Ident,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PrefixExpr {
     pub op: PrefixOp,
     pub val: Box<Expression>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InfixExpr {
     pub op: InfixOp,
     pub left: Box<Expression>,
     pub right: Box<Expression>,
 }
 
+/// `target = val`, where the target is a variable,
+/// an element like `xs[0]` or a field like `point.x`
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssignExpr {
+    pub target: Box<Expression>,
+    pub val: Box<Expression>,
+}
+
+/// `cond ? then : alt`
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TernaryExpr {
+    pub cond: Box<Expression>,
+    pub then: Box<Expression>,
+    pub alt: Box<Expression>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndexExpr {
     pub list: Box<Expression>,
-    pub pos: usize,
+    pub index: Box<Expression>,
+    /// `list?[index]` evaluates to `none` if the list is `none`
+    pub optional: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldExpr {
+    pub obj: Box<Expression>,
+    pub field: Ident,
+    /// `obj?.field` evaluates to `none` if the obj is `none`
+    pub optional: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SliceExpr {
+    pub list: Box<Expression>,
+    /// Defaults to the start of the list
+    pub start: Option<Box<Expression>>,
+    /// Defaults to the end of the list
+    pub end: Option<Box<Expression>>,
+    /// `list?[start..end]` evaluates to `none` if the list is `none`
+    pub optional: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CallExpr {
     // needs to be an expression
     // because of weird infix parsing
@@ -117,61 +216,117 @@ pub struct CallExpr {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ListExpr {
     pub list: Vec<Expression>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashExpr {
+    pub pairs: Vec<(Expression, Expression)>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IfExpr {
-    pub _type: IfType,
-    pub cond: Option<Box<Expression>>,
+    /// The `if` branch followed by all `else if` branches
+    pub branches: Vec<IfBranch>,
+    /// The `else` branch
+    pub alt: Option<BlockStmt>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IfBranch {
+    pub cond: Box<Expression>,
     pub block: BlockStmt,
-    pub alt: Option<Box<IfExpr>>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LoopExpr {
     pub _type: LoopType,
+    /// Loop variable of `for` loops
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ident: Option<Ident>,
+    /// Condition of `while` loops or the value that `for` loops iterate over
     pub cond: Option<Box<Expression>>,
     pub block: BlockStmt,
     pub alt: Option<Box<LoopExpr>>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WhenExpr {
     pub comp_val: Option<Box<Expression>>,
     pub cases: Vec<CaseStmt>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FuncExpr {
     pub ret_type: Option<Ident>,
     pub args: Vec<OptionallyTypedIdent>,
     pub block: BlockStmt,
 }
 
-// TODO: Finish this
+/// Expression with annotations in front of it, like `@memo func(n) {}`
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AnnotationExpr {
+    pub annotations: Vec<Annotation>,
+    pub val: Box<Expression>,
+}
+
+/// `@name` or `@name(args)` in front of a declaration or expression
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotation {
     pub name: Ident,
+    /// Empty if the annotation has no parentheses
+    pub args: Vec<Expression>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StructExpr {
     pub fields: Vec<OptionallyTypedIdent>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumExpr {
     pub consts: Ident,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BlockStmt {
     pub stmts: Vec<Statement>,
+    /// Line of each statement, like `Program::lines`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lines: Vec<usize>,
+}
+
+impl BlockStmt {
+    pub fn new(stmts: Vec<Statement>) -> Self {
+        Self {
+            stmts,
+            lines: Vec::new(),
+        }
+    }
+}
+
+/// Lines are ignored like for `Program`
+impl PartialEq for BlockStmt {
+    fn eq(&self, other: &Self) -> bool {
+        self.stmts == other.stmts
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CaseStmt {
     pub _type: CaseType,
     /// This should be Some(...) if the _type is Regular and
@@ -182,6 +337,7 @@ pub struct CaseStmt {
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PrefixOp {
     Pos,
     Neg,
@@ -189,6 +345,7 @@ pub enum PrefixOp {
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InfixOp {
     Add,
     Sub,
@@ -203,17 +360,11 @@ pub enum InfixOp {
     As,
     In,
     Range,
-    Assign,
-}
-
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum IfType {
-    If,
-    Else,
-    ElseIf,
+    NullCoalesce,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LoopType {
     For,
     While,
@@ -223,6 +374,7 @@ pub enum LoopType {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CaseType {
     Regular,
     Else,
@@ -249,9 +401,9 @@ impl Display for Statement {
                     var.name,
                     var.val,
                 ),
-                Statement::Return(ret) => todo!(),
-                Statement::Break(br) => todo!(),
-                Statement::Local(lcl) => todo!(),
+                Statement::Return(_) => todo!(),
+                Statement::Break(_) => todo!(),
+                Statement::Local(_) => todo!(),
                 Statement::Use(_use) => todo!(),
                 Statement::Expression(expr) => expr.to_string(),
             }
@@ -261,10 +413,15 @@ impl Display for Statement {
 
 impl Display for OptionallyTypedIdent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}", self.ident, match &self._type {
-            Some(_type) => format!(": {}", _type),
-            None => "".into()
-        })
+        write!(
+            f,
+            "{}{}",
+            self.ident,
+            match &self._type {
+                Some(_type) => format!(": {}", _type),
+                None => "".into(),
+            }
+        )
     }
 }
 
@@ -278,15 +435,68 @@ impl Display for Expression {
                 Expression::Literal(lit) => lit.to_string(),
                 Expression::Prefix(prefix) => prefix.to_string(),
                 Expression::Infix(infix) => infix.to_string(),
-                Expression::Index(_) => todo!(),
+                Expression::Assign(assign) => format!("{} = {}", assign.target, assign.val),
+                Expression::Ternary(ternary) =>
+                    format!("{} ? {} : {}", ternary.cond, ternary.then, ternary.alt),
+                Expression::Index(index) => format!(
+                    "{}{}[{}]",
+                    index.list,
+                    if index.optional { "?" } else { "" },
+                    index.index
+                ),
+                Expression::Field(field) => format!(
+                    "{}{}.{}",
+                    field.obj,
+                    if field.optional { "?" } else { "" },
+                    field.field
+                ),
+                Expression::Slice(slice) => format!(
+                    "{}{}[{}..{}]",
+                    slice.list,
+                    if slice.optional { "?" } else { "" },
+                    slice
+                        .start
+                        .as_ref()
+                        .map(|s| s.to_string())
+                        .unwrap_or_default(),
+                    slice
+                        .end
+                        .as_ref()
+                        .map(|e| e.to_string())
+                        .unwrap_or_default()
+                ),
                 //Expression::Call(call) => call.to_string(),
-                Expression::List(_) => todo!(),
+                Expression::List(list) => format!(
+                    "[{}]",
+                    list.list
+                        .iter()
+                        .map(|expr| expr.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                Expression::Hash(hash) => format!(
+                    "{{{}}}",
+                    hash.pairs
+                        .iter()
+                        .map(|(key, val)| format!("{}: {}", key, val))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
                 Expression::None => "none".into(),
                 //Expression::If(_if) => _if.to_string(),
                 //Expression::Loop(_loop) => _loop.to_string(),
                 Expression::When(_) => todo!(),
                 //Expression::Func(func) => func.to_string(),
-                Expression::Annotation(_) => todo!(),
+                Expression::Annotation(annotated) => format!(
+                    "{} {}",
+                    annotated
+                        .annotations
+                        .iter()
+                        .map(|annotation| annotation.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" "),
+                    annotated.val
+                ),
                 Expression::Struct(_) => todo!(),
                 Expression::Enum(_) => todo!(),
                 _ => todo!(),
@@ -295,6 +505,24 @@ impl Display for Expression {
     }
 }
 
+impl Display for Annotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{}", self.name)?;
+        if !self.args.is_empty() {
+            write!(
+                f,
+                "({})",
+                self.args
+                    .iter()
+                    .map(|arg| arg.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
 impl Display for PrefixExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -330,23 +558,9 @@ impl Display for InfixExpr {
                 InfixOp::As => "as",
                 InfixOp::In => "in",
                 InfixOp::Range => return write!(f, "{}..{}", self.left, self.right),
-                InfixOp::Assign => "=",
+                InfixOp::NullCoalesce => "??",
             },
             self.right
         )
     }
 }
-
-impl Display for OptionallyTypedIdent {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}{}",
-            self.ident,
-            match &self._type {
-                Some(_type) => format!(": {}", _type),
-                None => "".into(),
-            }
-        )
-    }
-}
@@ -0,0 +1,144 @@
+//! Assembly of one program out of multiple source files.
+//!
+//! Every source is parsed on its own and the statements are joined
+//! in the order the sources were added, so all files share the same
+//! globals and later files can use what earlier ones declared.
+//! A name may only be declared at the top level of one file.
+//! Redeclaring it inside of the same file is left to the evaluator,
+//! like it is for single files
+
+use std::{collections::HashMap, error::Error, fmt::Display, ops::Range};
+
+use super::{
+    ast::{Program, Statement},
+    parse_source_located, SyntaxError,
+};
+
+/// Line (starting at 1) of a named source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkError {
+    /// A source could not be parsed
+    Syntax {
+        err: SyntaxError,
+        location: Location,
+    },
+    /// Two sources declared the same name at their top level
+    DuplicateDefinition {
+        name: String,
+        first: Location,
+        second: Location,
+    },
+}
+
+impl Error for LinkError {}
+
+impl Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::Syntax { err, location } => write!(f, "{} at {}", err, location),
+            LinkError::DuplicateDefinition {
+                name,
+                first,
+                second,
+            } => write!(
+                f,
+                "{} is declared at {} and again at {}",
+                name, first, second
+            ),
+        }
+    }
+}
+
+/// The joined program together with where its statements came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkedProgram {
+    /// The lines of the statements are the lines inside of their own file
+    pub program: Program,
+    /// Name of every source with the indices of its statements in the program
+    pub files: Vec<(String, Range<usize>)>,
+}
+
+impl LinkedProgram {
+    /// File and line of the top level statement at the index
+    pub fn location(&self, stmt: usize) -> Option<Location> {
+        let (file, _) = self.files.iter().find(|(_, range)| range.contains(&stmt))?;
+        Some(Location {
+            file: file.clone(),
+            line: *self.program.lines.get(stmt)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProgramBuilder {
+    /// Names and source code in the order they were added
+    sources: Vec<(String, String)>,
+}
+
+impl ProgramBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a source that runs after all sources added before it
+    pub fn add_source(&mut self, name: impl Into<String>, source: impl Into<String>) -> &mut Self {
+        self.sources.push((name.into(), source.into()));
+        self
+    }
+
+    /// Parses every source and joins them into one program.
+    /// Stops at the first source that cannot be parsed
+    pub fn build(&self) -> Result<LinkedProgram, LinkError> {
+        let mut program = Program::new(Vec::new());
+        let mut files = Vec::new();
+        let mut declared: HashMap<String, Location> = HashMap::new();
+        for (name, source) in &self.sources {
+            let parsed = parse_source_located(source).map_err(|(err, line)| LinkError::Syntax {
+                err,
+                location: Location {
+                    file: name.clone(),
+                    line,
+                },
+            })?;
+            for (stmt, line) in parsed.stmts.iter().zip(&parsed.lines) {
+                let Statement::Variable(var) = stmt else {
+                    continue;
+                };
+                let location = Location {
+                    file: name.clone(),
+                    line: *line,
+                };
+                match declared.get(&var.name.ident.0) {
+                    Some(first) if first.file != *name => {
+                        return Err(LinkError::DuplicateDefinition {
+                            name: var.name.ident.0.clone(),
+                            first: first.clone(),
+                            second: location,
+                        })
+                    }
+                    Some(_) => (),
+                    None => {
+                        declared.insert(var.name.ident.0.clone(), location);
+                    }
+                }
+            }
+            let start = program.stmts.len();
+            program.stmts.extend(parsed.stmts);
+            program.lines.extend(parsed.lines);
+            files.push((name.clone(), start..program.stmts.len()));
+        }
+        Ok(LinkedProgram { program, files })
+    }
+}
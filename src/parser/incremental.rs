@@ -0,0 +1,191 @@
+//! Reparsing of only the statements that an edit touched.
+//!
+//! A [`Document`] keeps the source code together with its [`Program`].
+//! Top level statements are separated by the lines they start on, so an
+//! edit only needs to relex and reparse the statements whose lines it
+//! touched, plus the statement after them in case the edit changed
+//! where that one starts. The new statements are spliced into the program
+//! and the lines of all following statements are moved. If the touched
+//! region does not parse on its own, e.g. because a `}` was removed,
+//! the whole source is parsed again instead
+
+use std::ops::Range;
+
+use super::{
+    ast::{BlockStmt, Program, Statement},
+    parse_source,
+    visit::{self, VisitorMut},
+    SyntaxError,
+};
+
+/// Replaces the chars in `range` with `text`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    /// Positions counted in chars
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    source: String,
+    program: Program,
+    /// Whether the program is older than the source code
+    /// because the source code has a syntax error
+    stale: bool,
+}
+
+impl Document {
+    pub fn new(source: &str) -> Result<Self, SyntaxError> {
+        Ok(Self {
+            source: source.into(),
+            program: parse_source(source)?,
+            stale: false,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Program of the last source code that could be parsed.
+    /// Until the syntax error is fixed, edits parse the whole source code
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Position of a line (starting at 1) and column (in chars)
+    pub fn offset(&self, line: usize, col: usize) -> usize {
+        let line_start: usize = self
+            .source
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(|line| line.chars().count())
+            .sum();
+        line_start + col
+    }
+
+    /// Applies the edit to the source code and updates the program.
+    /// Returns the indices of the statements that were parsed again.
+    ///
+    /// If the new source code has a syntax error, the source code is
+    /// still changed, but the program stays the one before the edit
+    pub fn apply_edit(&mut self, edit: TextEdit) -> Result<Range<usize>, SyntaxError> {
+        let chars: Vec<char> = self.source.chars().collect();
+        let range = edit.range.start.min(chars.len())..edit.range.end.min(chars.len());
+        let start_line = line_of(&chars, range.start);
+        let end_line = line_of(&chars, range.end);
+        let line_delta = edit.text.matches('\n').count() as isize
+            - chars[range.clone()]
+                .iter()
+                .filter(|ch| **ch == '\n')
+                .count() as isize;
+
+        self.source = chars[..range.start]
+            .iter()
+            .chain(edit.text.chars().collect::<Vec<_>>().iter())
+            .chain(chars[range.end..].iter())
+            .collect();
+
+        let lines = &self.program.lines;
+        if self.stale || lines.len() != self.program.stmts.len() || lines.is_empty() {
+            return self.reparse_all();
+        }
+        let mut first = lines
+            .iter()
+            .rposition(|line| *line <= start_line)
+            .unwrap_or(0);
+        // Doc comments are above the line of their statement,
+        // so they are part of the region of the statement before
+        if first > 0 && has_docs(&self.program.stmts[first]) {
+            first -= 1;
+        }
+        // Statements separated by `;` share their line and
+        // the region is made of whole lines, so it has to start
+        // at the first and end after the last statement of a line
+        first = lines.partition_point(|line| *line < lines[first]);
+        let last = lines
+            .iter()
+            .rposition(|line| *line <= end_line)
+            .unwrap_or(0);
+        let mut end = (last.max(first) + 2).min(lines.len());
+        while end < lines.len() && lines[end] == lines[end - 1] {
+            end += 1;
+        }
+
+        let region_start = if first == 0 { 1 } else { lines[first] };
+        let region_end = lines.get(end).map(|line| shift(*line, line_delta));
+        let region: String = self
+            .source
+            .split_inclusive('\n')
+            .skip(region_start - 1)
+            .take(region_end.map_or(usize::MAX, |end| end - region_start))
+            .collect();
+
+        let Ok(mut reparsed) = parse_source(&region) else {
+            return self.reparse_all();
+        };
+        shift_program(&mut reparsed, region_start as isize - 1);
+        for stmt in &mut self.program.stmts[end..] {
+            shift_stmt(stmt, line_delta);
+        }
+        for line in &mut self.program.lines[end..] {
+            *line = shift(*line, line_delta);
+        }
+        let len = reparsed.stmts.len();
+        self.program.stmts.splice(first..end, reparsed.stmts);
+        self.program.lines.splice(first..end, reparsed.lines);
+        Ok(first..first + len)
+    }
+
+    fn reparse_all(&mut self) -> Result<Range<usize>, SyntaxError> {
+        self.stale = true;
+        self.program = parse_source(&self.source)?;
+        self.stale = false;
+        Ok(0..self.program.stmts.len())
+    }
+}
+
+/// Line (starting at 1) of the position
+fn line_of(chars: &[char], pos: usize) -> usize {
+    chars[..pos].iter().filter(|ch| **ch == '\n').count() + 1
+}
+
+fn shift(line: usize, delta: isize) -> usize {
+    line.saturating_add_signed(delta)
+}
+
+fn has_docs(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Variable(var) => var.docs.is_some(),
+        Statement::Local(local) => has_docs(&local.val),
+        _ => false,
+    }
+}
+
+fn shift_program(program: &mut Program, delta: isize) {
+    for line in &mut program.lines {
+        *line = shift(*line, delta);
+    }
+    for stmt in &mut program.stmts {
+        shift_stmt(stmt, delta);
+    }
+}
+
+/// Moves the lines of all blocks inside of the statement
+fn shift_stmt(stmt: &mut Statement, delta: isize) {
+    struct Shifter(isize);
+
+    impl VisitorMut for Shifter {
+        fn visit_block_mut(&mut self, block: &mut BlockStmt) {
+            for line in &mut block.lines {
+                *line = shift(*line, self.0);
+            }
+            visit::walk_block_mut(self, block);
+        }
+    }
+
+    if delta != 0 {
+        Shifter(delta).visit_stmt_mut(stmt);
+    }
+}
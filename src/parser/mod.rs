@@ -1,25 +1,63 @@
 pub mod ast;
+pub mod builder;
+pub mod incremental;
 mod tests;
+pub mod visit;
 
 use std::{error::Error, fmt::Display, mem::swap};
 
-use crate::{lexer::{tokens::{Token, Operator, Literal as TkLit}, Lexer}, util};
+use crate::{
+    codes::ErrorCode,
+    lexer::{
+        tokens::{Literal as TkLit, Operator, Token},
+        Lexer,
+    },
+    util,
+};
 use ast::*;
 
+/// Returns a syntax error with the code and the
+/// formatted message from the surrounding function
+macro_rules! raise {
+    ($code:expr, $($msg:tt)+) => {
+        return Err($crate::parser::SyntaxError::new($code, format!($($msg)+)))
+    };
+}
+pub(crate) use raise;
+
 pub struct Parser<'a> {
     lexer: &'a mut Lexer,
 
     cur_tok: Token,
     peek_tok: Token,
+    /// Lines where `cur_tok` and `peek_tok` start
+    cur_line: usize,
+    peek_line: usize,
+
+    /// Brackets that are currently open.
+    /// Newlines are ignored while the innermost one is `(` or `[`
+    /// so expressions can span multiple lines
+    brackets: Vec<Token>,
+    /// Doc comments for the next statement
+    docs: Option<String>,
 }
 
 #[repr(u8)]
+#[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum Precedence {
     /// default value
     Lowest,
     /// Assign new value to variable
     Assign,
+    /// Conditional operator
+    ///
+    /// `x > 0 ? x : -x`
+    Ternary,
+    /// Use the right value if the left one is none
+    ///
+    /// `name ?? "unknown"`
+    NullCoalesce,
     /// Check if i is in list/range
     ///
     /// `i in 0..10`
@@ -54,25 +92,161 @@ enum Precedence {
 }
 
 impl<'a> Parser<'a> {
+    /// Tokens are only read once parsing starts,
+    /// so errors of the lexer are returned by the parse functions
     pub fn new(lexer: &'a mut Lexer) -> Self {
-        let cur_tok = util::get_next_tok(lexer);
-        let peek_tok = util::get_next_tok(lexer);
         Self {
             lexer,
-            cur_tok,
-            peek_tok,
+            // Newlines are skipped in front of every statement and
+            // expression, which replaces these with the first tokens
+            cur_tok: Token::Eol,
+            peek_tok: Token::Eol,
+            cur_line: 1,
+            peek_line: 1,
+            brackets: Vec::new(),
+            docs: None,
+        }
+    }
+
+    /// Parses source code that consists of exactly one expression,
+    /// like a formula. Line breaks around the expression are allowed,
+    /// anything else after it is an error
+    pub fn parse_single_expression(source: &str) -> Result<Expression, SyntaxError> {
+        let mut lexer = Lexer::from_source(source);
+        let mut parser = Parser::new(&mut lexer);
+        parser.skip_eol()?;
+        if parser.cur_tok == Token::Eof {
+            raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected an expression, received: {}",
+                parser.cur_tok
+            )
+        }
+        let expr = parser.parse_expr(Precedence::Lowest)?;
+        parser.skip_peek_eol()?;
+        if parser.peek_tok != Token::Eof {
+            raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected the end of the expression, received: {}",
+                parser.peek_tok
+            )
+        }
+        Ok(expr)
+    }
+
+    /// Parses statements until the end of the file is reached
+    pub fn parse_program(&mut self) -> Result<Program, SyntaxError> {
+        let mut stmts = Vec::new();
+        let mut lines = Vec::new();
+        loop {
+            self.skip_eol()?;
+            self.parse_docs()?;
+            let line = self.cur_line;
+            match self.parse_stmt()? {
+                Some(stmt) => stmts.push(stmt),
+                None => break,
+            }
+            lines.push(line);
+            self.expect_stmt_end()?;
+            self.next_token()?;
+        }
+        Ok(Program { stmts, lines })
+    }
+
+    /// Parses the next statement, `None` at the end of the file.
+    /// Doc comments in front of it are attached if it declares a variable,
+    /// annotations if it declares a variable or is an expression
+    pub fn parse_stmt(&mut self) -> Result<Option<Statement>, SyntaxError> {
+        self.skip_eol()?;
+        self.parse_docs()?;
+        let docs = self.docs.take();
+        let annotations = self.parse_annotations()?;
+        let Some(mut stmt) = self.parse_stmt_kind()? else {
+            return Ok(None);
+        };
+        match &mut stmt {
+            Statement::Variable(var) => {
+                var.docs = docs;
+                var.annotations = annotations;
+            }
+            Statement::Local(local) => match local.val.as_mut() {
+                Statement::Variable(var) => {
+                    var.docs = docs;
+                    var.annotations = annotations;
+                }
+                _ => Self::expect_no_annotations(&annotations)?,
+            },
+            Statement::Expression(expr) if !annotations.is_empty() => {
+                let val = Box::new(std::mem::replace(expr, Expression::None));
+                *expr = Expression::Annotation(AnnotationExpr { annotations, val });
+            }
+            _ => Self::expect_no_annotations(&annotations)?,
+        }
+        Ok(Some(stmt))
+    }
+
+    /// Parses all annotations in front of a declaration or expression.
+    /// Newlines between them are allowed. Afterwards the current token
+    /// is the first token of the annotated code
+    fn parse_annotations(&mut self) -> Result<Vec<Annotation>, SyntaxError> {
+        let mut annotations = Vec::new();
+        while self.cur_tok == Token::At {
+            let name = match self.peek_tok {
+                Token::Ident(_) => Ident(self.peek_tok.to_string()),
+                ref other => raise!(
+                    ErrorCode::UnexpectedToken,
+                    "Expected the name of the annotation, received: {}",
+                    other
+                ),
+            };
+            self.next_token()?;
+            let args = match self.peek_tok {
+                Token::LParent => {
+                    self.next_token()?;
+                    self.parse_raw_list(Token::RParent)?
+                }
+                _ => Vec::new(),
+            };
+            self.next_token()?;
+            self.skip_eol()?;
+            if matches!(
+                self.cur_tok,
+                Token::Eof | Token::RCurly | Token::RParent | Token::RSquare | Token::Comma
+            ) {
+                raise!(
+                    ErrorCode::DanglingAnnotation,
+                    "Dangling annotation @{}, expected a declaration or expression after it",
+                    name
+                )
+            }
+            annotations.push(Annotation { name, args });
         }
+        Ok(annotations)
     }
 
-    pub fn parse_stmt(&mut self) -> Result<Statement, EofError> {
-        Ok(match self.cur_tok {
-            Token::Use => todo!(),
-            Token::Var => self.parse_variable(false),
-            Token::Const => self.parse_variable(true),
+    fn expect_no_annotations(annotations: &[Annotation]) -> Result<(), SyntaxError> {
+        if let Some(annotation) = annotations.first() {
+            raise!(
+                ErrorCode::InvalidSyntax,
+                "Annotations can only be attached to declarations and expressions, @{} is not",
+                annotation.name
+            )
+        }
+        Ok(())
+    }
+
+    fn parse_stmt_kind(&mut self) -> Result<Option<Statement>, SyntaxError> {
+        Ok(Some(match self.cur_tok {
+            Token::Use => raise!(
+                ErrorCode::InvalidSyntax,
+                "Use statements are not supported yet"
+            ),
+            Token::Var => self.parse_variable(false)?,
+            Token::Const => self.parse_variable(true)?,
             Token::Break => {
                 let label = match self.peek_tok {
                     Token::Ident(_) => {
-                        self.next_token();
+                        self.next_token()?;
                         Some(Ident(self.cur_tok.to_string()))
                     }
                     _ => None,
@@ -81,96 +255,101 @@ impl<'a> Parser<'a> {
             }
             Token::Return => {
                 let val = match self.peek_tok {
-                    Token::Eol => None,
+                    Token::Eol | Token::Eof | Token::RCurly => None,
                     _ => {
-                        self.next_token();
-                        Some(self.parse_expr(Precedence::Lowest))
+                        self.next_token()?;
+                        Some(self.parse_expr(Precedence::Lowest)?)
                     }
                 };
                 Statement::Return(ReturnStmt { val })
             }
             Token::Local => {
                 if self.peek_tok == Token::Local {
-                    panic!("Cannot stack multiple `local` statements")
+                    raise!(
+                        ErrorCode::StackedLocal,
+                        "Cannot stack multiple `local` statements"
+                    )
                 }
-                self.next_token();
-                let stmt = self.parse_stmt().expect(
-                    "Encountered End of file instead of a statement after the local statement",
-                );
+                self.next_token()?;
+                let Some(stmt) = self.parse_stmt()? else {
+                    raise!(
+                        ErrorCode::UnexpectedEof,
+                        "Encountered End of file instead of a statement after the local statement"
+                    )
+                };
                 Statement::Local(LocalStmt {
                     val: Box::new(stmt),
                 })
             }
             Token::Eol => {
-                self.next_token();
+                while self.cur_tok == Token::Eol {
+                    self.next_token()?;
+                }
                 return self.parse_stmt();
             }
-            Token::Eof => return Err(EofError),
+            Token::Eof => return Ok(None),
             _ => {
                 if let Token::Ident(_) = self.cur_tok {
                     if self.peek_tok == Token::VarAssign {
-                        return Ok(self.parse_quick_assign());
+                        return self.parse_quick_assign().map(Some);
                     }
                     match self.peek_tok {
                         Token::Colon | Token::ConstAssign | Token::VarAssign => {
-                            return Ok(self.parse_quick_assign())
+                            return self.parse_quick_assign().map(Some)
                         }
                         _ => (),
                     }
                 }
-                Statement::Expression(self.parse_expr(Precedence::Lowest))
+                Statement::Expression(self.parse_expr(Precedence::Lowest)?)
             }
-        })
+        }))
     }
 
-    fn parse_expr(&mut self, precedence: Precedence) -> Expression {
-        let prefix = self.parse_prefix();
-        if prefix.is_none() {
-            panic!("No prefix parse found for: {}", self.cur_tok)
-        }
-
-        let prefix = prefix.unwrap();
-        let mut left_expression = prefix;
+    fn parse_expr(&mut self, precedence: Precedence) -> Result<Expression, SyntaxError> {
+        let Some(mut left_expression) = self.parse_prefix()? else {
+            raise!(
+                ErrorCode::UnexpectedToken,
+                "No prefix parse found for: {}",
+                self.cur_tok
+            )
+        };
 
         while !self.peek_is_end() && precedence < self.get_precedence(&self.peek_tok) {
-            self.next_token();
-            // Unwrap here might not be safe. Observe this
-            left_expression = match self.parse_infix(left_expression) {
+            self.next_token()?;
+            left_expression = match self.parse_infix(left_expression)? {
                 Some(expr) => expr,
-                None => panic!("Invalid infix expression"),
+                None => raise!(ErrorCode::UnexpectedToken, "Invalid infix expression"),
             };
         }
 
-        left_expression
+        Ok(left_expression)
     }
 
-    fn parse_prefix(&mut self) -> Option<Expression> {
-        Some(match self.cur_tok {
+    fn parse_prefix(&mut self) -> Result<Option<Expression>, SyntaxError> {
+        Ok(Some(match self.cur_tok {
             Token::Ident(_) => Expression::Ident(Ident(self.cur_tok.to_string())),
             Token::Literal(TkLit::Bool(ref bool)) => Expression::Literal(Literal::Bool(*bool)),
+            Token::None => Expression::None,
             Token::Literal(TkLit::Num(ref lit)) => Expression::Literal(Literal::Num(*lit)),
+            Token::Literal(TkLit::Decimal(ref lit)) => Expression::Literal(Literal::Decimal(*lit)),
             Token::Literal(TkLit::Str(_)) => self.parse_str_lit(),
-            Token::LSquare => self.parse_list_lit(),
-            // TODO: add hashes
-            /*
-            Token::LCURLY => self.parse_hash_literal(),
-            */
-            // Token::NONE => Expression::NONE(NoneLiteral),
-            Token::LParent => self.parse_grouped_expr(),
-            Token::Func => self.parse_func_expr(),
-            Token::If => self.parse_if_expr(IfType::If),
-            Token::Loop => self.parse_loop_expr(),
-            Token::When => self.parse_when_expr(),
+            Token::LSquare => self.parse_list_lit()?,
+            Token::LCurly => self.parse_curly_expr()?,
+            Token::LParent => self.parse_grouped_expr()?,
+            Token::Func => self.parse_func_expr()?,
+            Token::If => self.parse_if_expr()?,
+            Token::Loop => self.parse_loop_expr()?,
+            Token::When => self.parse_when_expr()?,
             Token::ExclamMark
             | Token::Operator(Operator::Plus)
-            | Token::Operator(Operator::Minus) => self.parse_prefix_expr(),
-            // Token::ANNOTATION => self.parse_annotation(),
-            _ => return None,
-        })
+            | Token::Operator(Operator::Minus) => self.parse_prefix_expr()?,
+            Token::At => self.parse_annotation_expr()?,
+            _ => return Ok(None),
+        }))
     }
 
-    fn parse_infix(&mut self, left: Expression) -> Option<Expression> {
-        Some(match self.cur_tok {
+    fn parse_infix(&mut self, left: Expression) -> Result<Option<Expression>, SyntaxError> {
+        Ok(Some(match self.cur_tok {
             Token::Operator(ref op) => match op {
                 Operator::Equals
                 | Operator::NotEquals
@@ -181,12 +360,26 @@ impl<'a> Parser<'a> {
                 | Operator::Plus
                 | Operator::Minus
                 | Operator::Asterisk
-                | Operator::Slash => self.parse_infix_expr(left),
+                | Operator::Slash
+                | Operator::NullCoalesce => self.parse_infix_expr(left)?,
             },
-            Token::LParent => self.parse_call_expr(left),
-            // Token::LSquare => self.parse_index_expr(left),
-            _ => return None,
-        })
+            Token::LParent => self.parse_call_expr(left)?,
+            Token::Assign => self.parse_assign_expr(left)?,
+            Token::QuestionMark => self.parse_ternary_expr(left)?,
+            Token::LSquare => self.parse_index_expr(left, false)?,
+            Token::QuestionSquare => self.parse_index_expr(left, true)?,
+            Token::Dot => self.parse_field_expr(left, false)?,
+            Token::QuestionDot => self.parse_field_expr(left, true)?,
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Annotations inside of an expression apply to the whole
+    /// expression after them. Current token needs to be `@`
+    fn parse_annotation_expr(&mut self) -> Result<Expression, SyntaxError> {
+        let annotations = self.parse_annotations()?;
+        let val = Box::new(self.parse_expr(Precedence::Lowest)?);
+        Ok(Expression::Annotation(AnnotationExpr { annotations, val }))
     }
 
     fn parse_str_lit(&mut self) -> Expression {
@@ -194,152 +387,359 @@ impl<'a> Parser<'a> {
         Expression::Literal(Literal::Str(self.cur_tok.to_string()))
     }
 
-    fn parse_list_lit(&mut self) -> Expression {
-        todo!()
+    fn parse_list_lit(&mut self) -> Result<Expression, SyntaxError> {
+        let list = self.parse_raw_list(Token::RSquare)?;
+        Ok(Expression::List(ListExpr { list }))
+    }
+
+    /// A `{` in expression position is either a hash or a block.
+    /// It is a hash if it is empty or if its first expression
+    /// is followed by a colon, otherwise it is a block
+    fn parse_curly_expr(&mut self) -> Result<Expression, SyntaxError> {
+        self.skip_peek_eol()?;
+        match self.peek_tok {
+            Token::RCurly => return self.parse_hash_lit(None),
+            Token::Var | Token::Const | Token::Return | Token::Break | Token::Local | Token::At => {
+                return Ok(Expression::Block(self.parse_block_stmt()?))
+            }
+            _ => (),
+        }
+        self.next_token()?;
+        let first_line = self.cur_line;
+        let first = match (&self.cur_tok, &self.peek_tok) {
+            (Token::Ident(_), Token::VarAssign | Token::ConstAssign) => {
+                self.parse_quick_assign()?
+            }
+            _ => {
+                let expr = self.parse_expr(Precedence::Lowest)?;
+                if self.peek_tok == Token::Colon {
+                    return self.parse_hash_lit(Some(expr));
+                }
+                Statement::Expression(expr)
+            }
+        };
+        self.expect_stmt_end()?;
+        self.next_token()?;
+        Ok(Expression::Block(
+            self.parse_block_rest(vec![first], vec![first_line])?,
+        ))
+    }
+
+    /// Parses hashes like `{"name": "John", "age": 35}`.
+    /// The pairs can be split over multiple lines.
+    /// If the first key was already parsed, the current token
+    /// needs to be its last token
+    fn parse_hash_lit(&mut self, first_key: Option<Expression>) -> Result<Expression, SyntaxError> {
+        let mut pairs = Vec::new();
+        let mut key = first_key;
+        self.skip_peek_eol()?;
+        while key.is_some() || self.peek_tok != Token::RCurly {
+            let cur_key = match key.take() {
+                Some(key) => key,
+                None => {
+                    self.next_token()?;
+                    self.parse_expr(Precedence::Lowest)?
+                }
+            };
+            self.expect_peek(Token::Colon)?;
+            self.next_token()?;
+            self.next_token()?;
+            let val = self.parse_expr(Precedence::Lowest)?;
+            pairs.push((cur_key, val));
+            if self.peek_tok == Token::Comma {
+                self.next_token()?;
+            }
+            self.skip_peek_eol()?;
+        }
+        self.next_token()?;
+        Ok(Expression::Hash(HashExpr { pairs }))
+    }
+
+    /// Parses index expressions like `list[0]` as well as
+    /// slices like `list[1..3]`, `list[..2]` or `list[1..]`
+    fn parse_index_expr(
+        &mut self,
+        list: Expression,
+        optional: bool,
+    ) -> Result<Expression, SyntaxError> {
+        self.next_token()?;
+        let start = match self.cur_tok {
+            Token::DotDot => None,
+            _ => Some(Box::from(self.parse_expr(Precedence::Range)?)),
+        };
+        if let Some(index) = start {
+            if self.peek_tok != Token::DotDot {
+                self.expect_peek(Token::RSquare)?;
+                self.next_token()?;
+                return Ok(Expression::Index(IndexExpr {
+                    list: Box::from(list),
+                    index,
+                    optional,
+                }));
+            }
+            self.next_token()?;
+            return self.parse_slice_end(list, Some(index), optional);
+        }
+        self.parse_slice_end(list, None, optional)
     }
 
-    fn parse_grouped_expr(&mut self) -> Expression {
-        todo!()
+    /// Current token needs to be `..`
+    fn parse_slice_end(
+        &mut self,
+        list: Expression,
+        start: Option<Box<Expression>>,
+        optional: bool,
+    ) -> Result<Expression, SyntaxError> {
+        let end = match self.peek_tok {
+            Token::RSquare => None,
+            _ => {
+                self.next_token()?;
+                Some(Box::from(self.parse_expr(Precedence::Range)?))
+            }
+        };
+        self.expect_peek(Token::RSquare)?;
+        self.next_token()?;
+        Ok(Expression::Slice(SliceExpr {
+            list: Box::from(list),
+            start,
+            end,
+            optional,
+        }))
     }
 
-    fn parse_func_expr(&mut self) -> Expression {
-        self.expect_peek(Token::LParent);
-        self.next_token();
-        let args = self.parse_ident_list(Token::RParent);
+    /// Current token needs to be `.` or `?.`
+    fn parse_field_expr(
+        &mut self,
+        obj: Expression,
+        optional: bool,
+    ) -> Result<Expression, SyntaxError> {
+        let field = match self.peek_tok {
+            Token::Ident(_) => Ident(self.peek_tok.to_string()),
+            ref other => raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected a field name, received: {}",
+                other
+            ),
+        };
+        self.next_token()?;
+        Ok(Expression::Field(FieldExpr {
+            obj: Box::from(obj),
+            field,
+            optional,
+        }))
+    }
+
+    fn parse_grouped_expr(&mut self) -> Result<Expression, SyntaxError> {
+        raise!(
+            ErrorCode::InvalidSyntax,
+            "Grouped expressions are not supported yet"
+        )
+    }
+
+    fn parse_func_expr(&mut self) -> Result<Expression, SyntaxError> {
+        self.expect_peek(Token::LParent)?;
+        self.next_token()?;
+        let args = self.parse_ident_list(Token::RParent)?;
         let ret_type = match self.peek_tok {
             Token::Colon => {
-                self.next_token();
-                self.next_token();
+                self.next_token()?;
+                self.next_token()?;
                 Some(Ident(self.cur_tok.to_string()))
             }
             Token::LCurly => None,
-            _ => panic!(),
+            ref other => raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected the return type or the block of the function, received: {}",
+                other
+            ),
         };
-        self.next_token();
-        let block = self.parse_block_stmt();
-        Expression::Func(FuncExpr {
+        self.next_token()?;
+        let block = self.parse_block_stmt()?;
+        Ok(Expression::Func(FuncExpr {
             ret_type,
             args,
             block,
-        })
+        }))
     }
 
-    fn parse_if_expr(&mut self, _type: IfType) -> Expression {
-        match _type {
-            // Current token needs to be `if`
-            IfType::If => {
-                self.next_token();
-                let cond = self.parse_expr(Precedence::Lowest);
-                self.expect_peek(Token::LCurly);
-                self.next_token();
-                let block = self.parse_block_stmt();
-                let alt = match self.peek_tok {
-                    Token::Else => {
-                        self.next_token();
-                        Some(Box::from(match self.peek_tok {
-                            Token::If => match self.parse_if_expr(IfType::ElseIf) {
-                                Expression::If(_if) => _if,
-                                _ => panic!("UNREACHABLE"),
-                            },
-                            Token::LCurly => match self.parse_if_expr(IfType::Else) {
-                                Expression::If(_if) => _if,
-                                _ => panic!("UNREACHABLE"),
-                            },
-                            ref other => {
-                                panic!("Exptected `block` or `if` after else, got `{other:?}`")
-                            }
-                        }))
-                    }
-                    _ => None,
-                };
-                Expression::If(IfExpr {
-                    cond: Some(Box::from(cond)),
-                    block,
-                    _type,
-                    alt,
-                })
-            }
-            // Current token needs to be `else`
-            IfType::ElseIf => {
-                self.next_token();
-                let _if = match self.parse_if_expr(IfType::If) {
-                    Expression::If(_if) => _if,
-                    other => panic!("Unreachable: Got {other:?} instead of if expression"),
-                };
-                Expression::If(IfExpr { _type, .._if })
-            }
-            // Current token needs to be `else`
-            IfType::Else => {
-                self.next_token();
-                let block = self.parse_block_stmt();
-                Expression::If(IfExpr {
-                    _type,
-                    cond: None,
-                    block,
-                    alt: None,
-                })
+    /// Parses the whole `if`, `else if`, `else` chain.
+    /// Current token needs to be `if`
+    fn parse_if_expr(&mut self) -> Result<Expression, SyntaxError> {
+        let mut branches = vec![self.parse_if_branch()?];
+        let mut alt = None;
+        while self.peek_tok == Token::Else {
+            self.next_token()?;
+            match self.peek_tok {
+                Token::If => {
+                    self.next_token()?;
+                    branches.push(self.parse_if_branch()?);
+                }
+                Token::LCurly => {
+                    self.next_token()?;
+                    alt = Some(self.parse_block_stmt()?);
+                    break;
+                }
+                ref other => raise!(
+                    ErrorCode::UnexpectedToken,
+                    "Expected `block` or `if` after else, got `{other:?}`"
+                ),
             }
         }
+        Ok(Expression::If(IfExpr { branches, alt }))
     }
 
+    /// Current token needs to be `if`
+    fn parse_if_branch(&mut self) -> Result<IfBranch, SyntaxError> {
+        self.next_token()?;
+        let cond = self.parse_expr(Precedence::Lowest)?;
+        self.expect_peek(Token::LCurly)?;
+        self.next_token()?;
+        let block = self.parse_block_stmt()?;
+        Ok(IfBranch {
+            cond: Box::from(cond),
+            block,
+        })
+    }
+
+    /// `loop x in xs {}` is a `for` loop, `loop cond {}` a `while` loop.
+    /// Current token needs to be `loop`
     // TODO: parse else branches
-    fn parse_loop_expr(&mut self) -> Expression {
-        self.next_token();
-        let cond = self.parse_expr(Precedence::Lowest);
-        self.expect_peek(Token::LCurly);
-        self.next_token();
-        let block = self.parse_block_stmt();
-        Expression::Loop(LoopExpr {
+    fn parse_loop_expr(&mut self) -> Result<Expression, SyntaxError> {
+        self.next_token()?;
+        match (&self.cur_tok, &self.peek_tok) {
+            (Token::Ident(_), Token::In) => self.parse_for_expr(),
+            _ => self.parse_while_expr(),
+        }
+    }
+
+    /// Current token needs to be the start of the condition
+    fn parse_while_expr(&mut self) -> Result<Expression, SyntaxError> {
+        let cond = self.parse_expr(Precedence::Lowest)?;
+        self.expect_peek(Token::LCurly)?;
+        self.next_token()?;
+        let block = self.parse_block_stmt()?;
+        Ok(Expression::Loop(LoopExpr {
             _type: LoopType::While,
+            ident: None,
             cond: Some(Box::from(cond)),
             block,
             alt: None,
-        })
+        }))
     }
 
-    fn parse_when_expr(&mut self) -> Expression {
-        todo!()
+    /// Current token needs to be the loop variable
+    fn parse_for_expr(&mut self) -> Result<Expression, SyntaxError> {
+        let ident = Ident(self.cur_tok.to_string());
+        self.next_token()?;
+        self.next_token()?;
+        let iter = self.parse_expr(Precedence::Lowest)?;
+        self.expect_peek(Token::LCurly)?;
+        self.next_token()?;
+        let block = self.parse_block_stmt()?;
+        Ok(Expression::Loop(LoopExpr {
+            _type: LoopType::For,
+            ident: Some(ident),
+            cond: Some(Box::from(iter)),
+            block,
+            alt: None,
+        }))
+    }
+
+    fn parse_when_expr(&mut self) -> Result<Expression, SyntaxError> {
+        raise!(
+            ErrorCode::InvalidSyntax,
+            "When expressions are not supported yet"
+        )
     }
 
-    fn parse_infix_expr(&mut self, left_expr: Expression) -> Expression {
-        let op = match self.cur_tok {
-            Token::Operator(_) => self.cur_tok_to_in_op(),
-            ref other => panic!("Missing operator, got {other} instead"),
+    fn parse_infix_expr(&mut self, left_expr: Expression) -> Result<Expression, SyntaxError> {
+        let op = match &self.cur_tok {
+            Token::Operator(op) => Self::reg_op_to_in_op(op),
+            other => raise!(
+                ErrorCode::UnexpectedToken,
+                "Missing operator, got {other} instead"
+            ),
         };
         let prec = self.get_precedence(&self.cur_tok);
-        self.next_token();
-        let right_expr = self.parse_expr(prec);
-        Expression::Infix(InfixExpr {
+        self.next_token()?;
+        self.skip_eol()?;
+        let right_expr = self.parse_expr(prec)?;
+        Ok(Expression::Infix(InfixExpr {
             left: Box::from(left_expr),
             right: Box::from(right_expr),
             op,
-        })
+        }))
     }
 
-    fn parse_prefix_expr(&mut self) -> Expression {
-        let op = match &self.cur_tok {
-            Token::Operator(op) => Self::reg_op_to_pre_op(op),
-            Token::ExclamMark => PrefixOp::Not,
-            other => panic!("Expected operator, got: {other} instead"),
-        };
-        self.next_token();
-        let val = Box::from(self.parse_expr(Precedence::Prefix));
-        Expression::Prefix(PrefixExpr { op, val })
+    /// Current token needs to be `=`
+    fn parse_assign_expr(&mut self, target: Expression) -> Result<Expression, SyntaxError> {
+        if !Self::is_assignable(&target) {
+            raise!(
+                ErrorCode::InvalidAssignTarget,
+                "Invalid assignment target, expected a variable, an element or a field"
+            )
+        }
+        self.next_token()?;
+        self.skip_eol()?;
+        // Parse with the lowest precedence to make the operator right associative
+        let val = self.parse_expr(Precedence::Lowest)?;
+        Ok(Expression::Assign(AssignExpr {
+            target: Box::from(target),
+            val: Box::from(val),
+        }))
     }
 
-    fn cur_tok_to_in_op(&self) -> InfixOp {
-        match &self.cur_tok {
-            Token::Operator(op) => Self::reg_op_to_in_op(op),
-            _ => todo!(),
+    /// Variables and elements or fields of them, `xs?[0]` and `p?.x` are not
+    fn is_assignable(target: &Expression) -> bool {
+        match target {
+            Expression::Ident(_) => true,
+            Expression::Index(index) => !index.optional && Self::is_assignable(&index.list),
+            Expression::Field(field) => !field.optional && Self::is_assignable(&field.obj),
+            _ => false,
         }
     }
 
-    fn reg_op_to_pre_op(op: &Operator) -> PrefixOp {
-        match op {
+    /// Current token needs to be `?`
+    fn parse_ternary_expr(&mut self, cond: Expression) -> Result<Expression, SyntaxError> {
+        self.next_token()?;
+        self.skip_eol()?;
+        let then = self.parse_expr(Precedence::Lowest)?;
+        self.expect_peek(Token::Colon)?;
+        self.next_token()?;
+        self.next_token()?;
+        self.skip_eol()?;
+        // Parse with a lower precedence to make the operator right associative
+        let alt = self.parse_expr(Precedence::Assign)?;
+        Ok(Expression::Ternary(TernaryExpr {
+            cond: Box::from(cond),
+            then: Box::from(then),
+            alt: Box::from(alt),
+        }))
+    }
+
+    fn parse_prefix_expr(&mut self) -> Result<Expression, SyntaxError> {
+        let op = match &self.cur_tok {
+            Token::Operator(op) => Self::reg_op_to_pre_op(op)?,
+            Token::ExclamMark => PrefixOp::Not,
+            other => raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected operator, got: {other} instead"
+            ),
+        };
+        self.next_token()?;
+        let val = Box::from(self.parse_expr(Precedence::Prefix)?);
+        Ok(Expression::Prefix(PrefixExpr { op, val }))
+    }
+
+    fn reg_op_to_pre_op(op: &Operator) -> Result<PrefixOp, SyntaxError> {
+        Ok(match op {
             Operator::Plus => PrefixOp::Pos,
             Operator::Minus => PrefixOp::Neg,
-            other => panic!("Cannot convert operator: {other} to pre op"),
-        }
+            other => raise!(
+                ErrorCode::UnexpectedToken,
+                "Cannot convert operator: {other} to pre op"
+            ),
+        })
     }
 
     fn reg_op_to_in_op(op: &Operator) -> InfixOp {
@@ -354,157 +754,196 @@ impl<'a> Parser<'a> {
             Operator::Minus => InfixOp::Sub,
             Operator::Asterisk => InfixOp::Mul,
             Operator::Slash => InfixOp::Div,
+            Operator::NullCoalesce => InfixOp::NullCoalesce,
         }
     }
 
     /// First token needs to be the begin_token like `(` or `{` for example
     /// This function sets cur_tok to the end_tok
-    fn parse_ident_list(&mut self, end_tok: Token) -> Vec<OptionallyTypedIdent> {
+    fn parse_ident_list(
+        &mut self,
+        end_tok: Token,
+    ) -> Result<Vec<OptionallyTypedIdent>, SyntaxError> {
         if self.peek_tok == end_tok {
-            self.next_token();
-            return Vec::new();
+            self.next_token()?;
+            return Ok(Vec::new());
         }
 
-        self.next_token();
+        self.next_token()?;
 
         let mut items = Vec::new();
 
-        let first_item = self.parse_typed_ident();
+        let first_item = self.parse_typed_ident()?;
 
         items.push(first_item);
 
         if self.peek_tok == Token::Comma {
-            self.next_token();
+            self.next_token()?;
         }
 
         while self.peek_tok != end_tok {
-            self.next_token();
-            let ident = self.parse_typed_ident();
+            self.next_token()?;
+            let ident = self.parse_typed_ident()?;
             items.push(ident);
             if self.peek_tok == Token::Comma {
-                self.next_token();
+                self.next_token()?;
             }
         }
 
-        self.next_token();
-        items
+        self.next_token()?;
+        Ok(items)
     }
 
     /// First token needs to be the begin_token like `(` or `{` for example
-    fn parse_raw_list(&mut self, end_tok: Token) -> Vec<Expression> {
-        if self.peek_tok == Token::RParent {
-            self.next_token();
-            return Vec::new();
+    fn parse_raw_list(&mut self, end_tok: Token) -> Result<Vec<Expression>, SyntaxError> {
+        if self.peek_tok == end_tok {
+            self.next_token()?;
+            return Ok(Vec::new());
         }
 
-        self.next_token();
+        self.next_token()?;
 
         let mut items = Vec::new();
 
-        let first_item = self.parse_expr(Precedence::Lowest);
+        let first_item = self.parse_expr(Precedence::Lowest)?;
 
         items.push(first_item);
 
         if self.peek_tok == Token::Comma {
-            self.next_token();
+            self.next_token()?;
         }
 
         while self.peek_tok != end_tok {
-            self.next_token();
-            let ident = self.parse_expr(Precedence::Lowest);
+            self.next_token()?;
+            let ident = self.parse_expr(Precedence::Lowest)?;
             items.push(ident);
             if self.peek_tok == Token::Comma {
-                self.next_token();
+                self.next_token()?;
             }
         }
 
-        self.next_token();
-        items
+        self.next_token()?;
+        Ok(items)
     }
 
     /// First token needs to be a left curly `{`
-    fn parse_block_stmt(&mut self) -> BlockStmt {
-        let mut stmts = Vec::new();
+    /// This function sets cur_tok to the closing curly `}`
+    fn parse_block_stmt(&mut self) -> Result<BlockStmt, SyntaxError> {
+        self.next_token()?;
+        self.parse_block_rest(Vec::new(), Vec::new())
+    }
 
-        self.next_token();
-        while self.peek_tok != Token::RCurly {
-            self.next_token();
-            let stmt = self
-                .parse_stmt()
-                .expect("Found eof even though the blockstatement was not yet fully parsed");
+    /// Parses the remaining statements of a block
+    /// until the closing curly `}` is the current token
+    fn parse_block_rest(
+        &mut self,
+        mut stmts: Vec<Statement>,
+        mut lines: Vec<usize>,
+    ) -> Result<BlockStmt, SyntaxError> {
+        while self.cur_tok != Token::RCurly {
+            if self.cur_tok == Token::Eol {
+                self.next_token()?;
+                continue;
+            }
+            if let Token::DocComment(_) = self.cur_tok {
+                self.parse_docs()?;
+                continue;
+            }
+            lines.push(self.cur_line);
+            let Some(stmt) = self.parse_stmt()? else {
+                raise!(
+                    ErrorCode::UnexpectedEof,
+                    "Found eof even though the blockstatement was not yet fully parsed"
+                )
+            };
             stmts.push(stmt);
-            self.next_token();
+            self.expect_stmt_end()?;
+            self.next_token()?;
         }
-        self.next_token();
-        BlockStmt { stmts }
+        Ok(BlockStmt { stmts, lines })
     }
 
-    fn parse_typed_ident(&mut self) -> OptionallyTypedIdent {
+    fn parse_typed_ident(&mut self) -> Result<OptionallyTypedIdent, SyntaxError> {
         let ident = Ident(self.cur_tok.to_string());
         let _type = match self.peek_tok {
             Token::Colon => {
-                self.next_token();
-                self.next_token();
+                self.next_token()?;
+                self.next_token()?;
                 Some(Ident(self.cur_tok.to_string()))
             }
             _ => None,
         };
-        OptionallyTypedIdent { ident, _type }
+        Ok(OptionallyTypedIdent { ident, _type })
     }
 
-    fn parse_variable(&mut self, is_const: bool) -> Statement {
+    fn parse_variable(&mut self, is_const: bool) -> Result<Statement, SyntaxError> {
         let name = Ident(match self.peek_tok {
             Token::Ident(_) => self.peek_tok.to_string(),
-            _ => panic!("Expected an identifier, received: {}", self.peek_tok),
+            _ => raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected an identifier, received: {}",
+                self.peek_tok
+            ),
         });
 
-        self.next_token();
+        self.next_token()?;
 
         let _type = match self.peek_tok {
             Token::Colon => {
-                self.next_token();
+                self.next_token()?;
                 let ident = Ident(self.peek_tok.to_string());
-                self.next_token();
-                self.expect_peek(Token::Assign);
-                self.next_token();
+                self.next_token()?;
+                self.expect_peek(Token::Assign)?;
+                self.next_token()?;
                 Some(ident)
             }
             Token::Assign => {
-                self.next_token();
+                self.next_token()?;
                 None
             }
-            _ => panic!("Expected Assign, received: {}", self.peek_tok),
+            _ => raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected Assign, received: {}",
+                self.peek_tok
+            ),
         };
 
-        self.next_token();
+        self.next_token()?;
 
-        let val = self.parse_expr(Precedence::Lowest);
+        let val = self.parse_expr(Precedence::Lowest)?;
 
-        Statement::Variable(VarStmt {
+        Ok(Statement::Variable(VarStmt {
             name: OptionallyTypedIdent { ident: name, _type },
             val,
             is_const,
-        })
+            docs: None,
+            annotations: Vec::new(),
+        }))
     }
 
-    fn parse_quick_assign(&mut self) -> Statement {
+    fn parse_quick_assign(&mut self) -> Result<Statement, SyntaxError> {
         let name = Ident(match self.cur_tok {
             Token::Ident(_) => self.cur_tok.to_string(),
             // unreachable
-            _ => panic!("Expected an identifier, received: {}", self.cur_tok),
+            _ => raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected an identifier, received: {}",
+                self.cur_tok
+            ),
         });
 
         let is_const;
 
         let _type = match self.peek_tok {
             Token::Colon => {
-                self.next_token();
+                self.next_token()?;
                 let ident = Ident(self.peek_tok.to_string());
-                self.next_token();
+                self.next_token()?;
                 match self.peek_tok {
                     Token::ConstAssign => is_const = true,
                     Token::VarAssign => is_const = false,
-                    _ => panic!(
+                    _ => raise!(
+                        ErrorCode::UnexpectedToken,
                         "Expected ConstAssign or VarAssign, received: {}",
                         self.peek_tok
                     ),
@@ -519,42 +958,121 @@ impl<'a> Parser<'a> {
                 is_const = false;
                 None
             }
-            _ => panic!("Expected Assign, received: {}", self.peek_tok),
+            _ => raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected Assign, received: {}",
+                self.peek_tok
+            ),
         };
 
-        self.next_token();
-        self.next_token();
+        self.next_token()?;
+        self.next_token()?;
 
-        let val = self.parse_expr(Precedence::Lowest);
+        let val = self.parse_expr(Precedence::Lowest)?;
 
-        Statement::Variable(VarStmt {
+        Ok(Statement::Variable(VarStmt {
             name: OptionallyTypedIdent { ident: name, _type },
             val,
             is_const,
-        })
+            docs: None,
+            annotations: Vec::new(),
+        }))
     }
 
-    fn parse_call_expr(&mut self, func: Expression) -> Expression {
-        let args = self.parse_raw_list(Token::RParent);
-        Expression::Call(CallExpr {
+    fn parse_call_expr(&mut self, func: Expression) -> Result<Expression, SyntaxError> {
+        let args = self.parse_raw_list(Token::RParent)?;
+        Ok(Expression::Call(CallExpr {
             ident: Box::from(func),
             args,
-        })
+        }))
     }
 
-    fn expect_peek(&self, expected: Token) {
+    fn expect_peek(&self, expected: Token) -> Result<(), SyntaxError> {
         if self.peek_tok != expected {
-            panic!("Expected: {}, received: {}", expected, self.peek_tok)
+            raise!(
+                ErrorCode::UnexpectedToken,
+                "Expected: {}, received: {}",
+                expected,
+                self.peek_tok
+            )
+        }
+        Ok(())
+    }
+
+    /// Statements need to be terminated by a newline or `;`,
+    /// the end of the file or the end of the surrounding block
+    fn expect_stmt_end(&self) -> Result<(), SyntaxError> {
+        if !matches!(self.peek_tok, Token::Eol | Token::Eof | Token::RCurly) {
+            raise!(
+                ErrorCode::MissingStatementEnd,
+                "Expected the end of the statement (newline or `;`), received: {}",
+                self.peek_tok
+            )
         }
+        Ok(())
+    }
+
+    fn skip_peek_eol(&mut self) -> Result<(), SyntaxError> {
+        while self.peek_tok == Token::Eol {
+            self.next_token()?;
+        }
+        Ok(())
     }
 
     fn peek_is_end(&self) -> bool {
         matches!(self.peek_tok, Token::Eol | Token::Eof)
     }
 
-    pub fn next_token(&mut self) {
+    pub fn next_token(&mut self) -> Result<(), SyntaxError> {
         swap(&mut self.cur_tok, &mut self.peek_tok);
-        self.peek_tok = util::get_next_tok(self.lexer);
+        self.cur_line = self.peek_line;
+        match self.cur_tok {
+            Token::LParent | Token::LSquare | Token::QuestionSquare | Token::LCurly => {
+                self.brackets.push(self.cur_tok.clone())
+            }
+            Token::RParent | Token::RSquare | Token::RCurly => {
+                self.brackets.pop();
+            }
+            _ => (),
+        }
+        self.peek_tok = util::get_next_tok(self.lexer)?;
+        while self.peek_tok == Token::Eol && self.in_parens() {
+            self.peek_tok = util::get_next_tok(self.lexer)?;
+        }
+        self.peek_line = self.lexer.token_line();
+        Ok(())
+    }
+
+    fn in_parens(&self) -> bool {
+        matches!(
+            self.brackets.last(),
+            Some(Token::LParent | Token::LSquare | Token::QuestionSquare)
+        )
+    }
+
+    /// Collects the doc comments, so the next
+    /// variable declaration can take them
+    fn parse_docs(&mut self) -> Result<(), SyntaxError> {
+        while let Token::DocComment(doc) = &self.cur_tok {
+            match &mut self.docs {
+                Some(docs) => {
+                    docs.push('\n');
+                    docs.push_str(doc);
+                }
+                None => self.docs = Some(doc.clone()),
+            }
+            self.next_token()?;
+            self.skip_eol()?;
+        }
+        Ok(())
+    }
+
+    /// Skips newlines after a trailing operator
+    fn skip_eol(&mut self) -> Result<(), SyntaxError> {
+        while self.cur_tok == Token::Eol {
+            self.next_token()?;
+        }
+        Ok(())
     }
 
     fn get_precedence(&self, token: &Token) -> Precedence {
@@ -566,21 +1084,53 @@ impl<'a> Parser<'a> {
                 Operator::GreaterEquals | Operator::LesserEquals => Precedence::LessGreaterOrEqual,
                 Operator::Plus | Operator::Minus => Precedence::Sum,
                 Operator::Asterisk | Operator::Slash => Precedence::Product,
+                Operator::NullCoalesce => Precedence::NullCoalesce,
             },
+            Token::QuestionMark => Precedence::Ternary,
             Token::LParent => Precedence::Call,
-            Token::LSquare => Precedence::Index,
+            Token::LSquare | Token::QuestionSquare | Token::Dot | Token::QuestionDot => {
+                Precedence::Index
+            }
             _ => Precedence::Lowest,
         }
     }
 }
 
-#[derive(Debug)]
-pub struct EofError;
+/// Syntax error of a program
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub msg: String,
+    code: ErrorCode,
+}
 
-impl Error for EofError {}
+impl SyntaxError {
+    pub fn new(code: ErrorCode, msg: String) -> Self {
+        Self { msg, code }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+}
+
+impl Error for SyntaxError {}
 
-impl Display for EofError {
+impl Display for SyntaxError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str("Encountered end of file")
+        f.write_str(&self.msg)
     }
 }
+
+/// Parses the whole source code
+pub fn parse_source(source: &str) -> Result<Program, SyntaxError> {
+    parse_source_located(source).map_err(|(err, _)| err)
+}
+
+/// Like [`parse_source`], but also returns the line where parsing failed.
+/// This is the line of the last token the parser looked at,
+/// which can be one token after the actual mistake
+pub fn parse_source_located(source: &str) -> Result<Program, (SyntaxError, usize)> {
+    let mut lexer = Lexer::from_source(source);
+    let program = Parser::new(&mut lexer).parse_program();
+    program.map_err(|err| (err, lexer.token_line()))
+}
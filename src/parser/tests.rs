@@ -1,12 +1,416 @@
 #[cfg(test)]
 mod tests {
-    use crate::{lexer::Lexer, parser::Parser};
+    use crate::{
+        codes::ErrorCode,
+        evaluator::{objects::Object, Evaluator},
+        lexer::Lexer,
+        parser::{
+            ast::{
+                Annotation, BlockStmt, Expression, Ident, Literal, LoopExpr, LoopType, Program,
+                Statement,
+            },
+            builder::{LinkError, Location, ProgramBuilder},
+            incremental::{Document, TextEdit},
+            parse_source,
+            visit::{Visitor, VisitorMut},
+            Parser,
+        },
+    };
 
     #[test]
     fn test_parser() {
         let mut lexer = Lexer::new(&"tests/parser/test.nx".into()).expect("Invalid file path");
         let mut parser = Parser::new(&mut lexer);
         let stmt = parser.parse_stmt().expect("Failed to parse");
-        println!("{:#?}", stmt);
+        println!("{:#?}", stmt.expect("Encountered eof"));
+    }
+
+    /// Test for checking if `else if` chains are
+    /// parsed into a flat list of branches
+    #[test]
+    fn test_if_chain() {
+        let mut lexer = Lexer::new(&"tests/parser/if.nx".into()).expect("Invalid file path");
+        let mut parser = Parser::new(&mut lexer);
+        let _if = match parser.parse_stmt().expect("Failed to parse") {
+            Some(Statement::Expression(Expression::If(_if))) => _if,
+            other => panic!("Expected an if expression, got {:?}", other),
+        };
+        let conds: Vec<String> = _if.branches.iter().map(|b| b.cond.to_string()).collect();
+        assert_eq!(vec!["a", "b", "c"], conds);
+        assert_eq!(1, _if.alt.expect("Missing else branch").stmts.len());
+    }
+
+    /// Test for checking if statements can be separated by `;`
+    /// and if the file can end without a newline
+    #[test]
+    fn test_semicolons() {
+        let mut lexer =
+            Lexer::new(&"tests/parser/semicolons.nx".into()).expect("Invalid file path");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        assert_eq!(5, program.stmts.len());
+        assert_eq!("var a = 1", program.stmts[0].to_string());
+        assert_eq!("a + b", program.stmts[4].to_string());
+        assert_eq!(vec![1, 1, 2, 3, 5], program.lines);
+    }
+
+    /// Test for checking if two statements on the
+    /// same line without a `;` are rejected
+    #[test]
+    fn test_missing_terminator() {
+        let mut lexer =
+            Lexer::new(&"tests/parser/missing_terminator.nx".into()).expect("Invalid file path");
+        let err = Parser::new(&mut lexer).parse_program().unwrap_err();
+        assert!(err.msg.starts_with("Expected the end of the statement"));
+    }
+
+    /// Test for checking if the syntax tree survives
+    /// a round trip through json
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        use crate::parser::ast::Program;
+
+        let mut lexer = Lexer::new(&"tests/fmt/formatted.nx".into()).expect("Invalid file path");
+        let program = Parser::new(&mut lexer).parse_program().unwrap();
+        let json = serde_json::to_string(&program).expect("Failed to serialize");
+        assert!(json.contains(r#"{"Ident":"double"}"#));
+        let parsed: Program = serde_json::from_str(&json).expect("Failed to deserialize");
+        assert_eq!(program, parsed);
+    }
+
+    /// Test for checking if visitors reach every identifier
+    /// and if mutable visitors can change them
+    #[test]
+    fn test_visitor() {
+        #[derive(Default)]
+        struct IdentCollector(Vec<String>);
+
+        impl Visitor for IdentCollector {
+            fn visit_ident(&mut self, ident: &Ident) {
+                self.0.push(ident.0.clone());
+            }
+        }
+
+        struct Renamer;
+
+        impl VisitorMut for Renamer {
+            fn visit_ident_mut(&mut self, ident: &mut Ident) {
+                if ident.0 == "x" {
+                    ident.0 = "renamed".into();
+                }
+            }
+        }
+
+        let mut program = parse_source("var y = x ? [x] : {\"k\": f(x)?[0]}").unwrap();
+        let mut collector = IdentCollector::default();
+        collector.visit_program(&program);
+        assert_eq!(vec!["x", "x", "f", "x"], collector.0);
+
+        Renamer.visit_program_mut(&mut program);
+        let mut collector = IdentCollector::default();
+        collector.visit_program(&program);
+        assert_eq!(vec!["renamed", "renamed", "f", "renamed"], collector.0);
+    }
+
+    /// Test for checking if edits only reparse the statements
+    /// they touched and the result matches a full parse
+    #[test]
+    fn test_incremental() {
+        let source = std::fs::read_to_string("tests/parser/incremental.nx").unwrap();
+        let mut doc = Document::new(&source).unwrap();
+
+        // Inside of the function body. The statement before
+        // is parsed again as well, because it contains the doc comment
+        let pos = doc.offset(5, 8);
+        assert_eq!(
+            Ok(0..3),
+            doc.apply_edit(TextEdit {
+                range: pos..pos + 1,
+                text: "3\n    n".into(),
+            })
+        );
+        assert_same_as_full_parse(&doc);
+
+        // New lines in front of everything
+        assert_eq!(
+            Ok(0..3),
+            doc.apply_edit(TextEdit {
+                range: 0..0,
+                text: "var w = 0\n\n".into(),
+            })
+        );
+        assert_same_as_full_parse(&doc);
+
+        // Doc comments belong to the statement below them
+        let pos = doc.offset(4, 4);
+        assert_eq!(
+            Ok(1..3),
+            doc.apply_edit(TextEdit {
+                range: pos..pos + 7,
+                text: "Triples".into(),
+            })
+        );
+        assert_same_as_full_parse(&doc);
+
+        // The last statement
+        let pos = doc.offset(12, 6);
+        assert_eq!(
+            Ok(4..5),
+            doc.apply_edit(TextEdit {
+                range: pos..pos + 1,
+                text: "x".into(),
+            })
+        );
+        assert_same_as_full_parse(&doc);
+    }
+
+    /// Test for checking if statements that share a line
+    /// with others are parsed again together with them
+    #[test]
+    fn test_incremental_semicolons() {
+        let mut doc = Document::new("var a = 1; var b = 2\nvar c = 3\n").unwrap();
+        let pos = doc.offset(1, 19);
+        assert_eq!(
+            Ok(0..3),
+            doc.apply_edit(TextEdit {
+                range: pos..pos + 1,
+                text: "5".into(),
+            })
+        );
+        assert_eq!("var a = 1; var b = 5\nvar c = 3\n", doc.source());
+        assert_same_as_full_parse(&doc);
+
+        let mut doc = Document::new("var a = 1\nvar b = 2; var c = 3\nvar d = 4").unwrap();
+        let pos = doc.offset(1, 8);
+        assert_eq!(
+            Ok(0..3),
+            doc.apply_edit(TextEdit {
+                range: pos..pos + 1,
+                text: "7".into(),
+            })
+        );
+        assert_same_as_full_parse(&doc);
+    }
+
+    /// Test for checking if syntax errors keep the last program
+    /// until the whole source code can be parsed again
+    #[test]
+    fn test_incremental_error() {
+        let source = "var x = 1\ndouble :: func(n) {\n    n * 2\n}\nvar y = 2\nvar z = 3";
+        let mut doc = Document::new(source).unwrap();
+        let pos = doc.offset(4, 0);
+        assert!(doc
+            .apply_edit(TextEdit {
+                range: pos..pos + 1,
+                text: "".into(),
+            })
+            .is_err());
+        assert_eq!(4, doc.program().stmts.len());
+        assert_eq!(
+            Ok(0..4),
+            doc.apply_edit(TextEdit {
+                range: pos..pos,
+                text: "}".into(),
+            })
+        );
+        assert_same_as_full_parse(&doc);
+    }
+
+    /// Test for checking if `for` loops get their loop variable
+    /// and loops without one are `while` loops
+    #[test]
+    fn test_loops() {
+        let source = std::fs::read_to_string("tests/parser/loops.nx").unwrap();
+        let program = parse_source(&source).unwrap();
+        let loops: Vec<&LoopExpr> = program
+            .stmts
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Expression(Expression::Loop(_loop)) => _loop,
+                other => panic!("Expected a loop, got {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(LoopType::For, loops[0]._type);
+        assert_eq!(Some(Ident("item".into())), loops[0].ident);
+        assert!(matches!(
+            loops[0].cond.as_deref(),
+            Some(Expression::List(_))
+        ));
+        assert_eq!(1, loops[0].block.stmts.len());
+
+        assert_eq!(LoopType::While, loops[1]._type);
+        assert_eq!(None, loops[1].ident);
+        assert!(matches!(
+            loops[1].cond.as_deref(),
+            Some(Expression::Infix(_))
+        ));
+
+        assert!(parse_source("loop x in {}").is_err());
+    }
+
+    /// Test for checking if annotations are attached to the declaration
+    /// or expression after them and if dangling ones are rejected
+    #[test]
+    fn test_annotations() {
+        let source = std::fs::read_to_string("tests/parser/annotations.nx").unwrap();
+        let program = parse_source(&source).unwrap();
+        assert_eq!(3, program.stmts.len());
+
+        let Statement::Variable(add) = &program.stmts[0] else {
+            panic!("Expected a declaration, got {:?}", program.stmts[0]);
+        };
+        assert_eq!(Some("Adds two numbers".into()), add.docs);
+        assert_eq!(
+            vec![
+                Annotation {
+                    name: Ident("inline".into()),
+                    args: Vec::new(),
+                },
+                Annotation {
+                    name: Ident("deprecated".into()),
+                    args: vec![
+                        Expression::Literal(Literal::Str("use sum".into())),
+                        Expression::Literal(Literal::Num(2.0)),
+                    ],
+                },
+            ],
+            add.annotations
+        );
+
+        let Statement::Expression(Expression::Annotation(trace)) = &program.stmts[1] else {
+            panic!(
+                "Expected an annotated expression, got {:?}",
+                program.stmts[1]
+            );
+        };
+        assert_eq!(Ident("trace".into()), trace.annotations[0].name);
+        assert!(matches!(*trace.val, Expression::Call(_)));
+
+        let Statement::Variable(cached) = &program.stmts[2] else {
+            panic!("Expected a declaration, got {:?}", program.stmts[2]);
+        };
+        assert!(cached.annotations.is_empty());
+        assert!(matches!(cached.val, Expression::Annotation(_)));
+
+        let code = |source: &str| parse_source(source).unwrap_err().code();
+        assert_eq!(ErrorCode::DanglingAnnotation, code("var x = 1\n@inline"));
+        assert_eq!(ErrorCode::DanglingAnnotation, code("{ @inline }"));
+        assert_eq!(ErrorCode::DanglingAnnotation, code("print(@inline)"));
+        assert_eq!(
+            ErrorCode::InvalidSyntax,
+            code("func() { @inline return 1 }")
+        );
+        assert_eq!(ErrorCode::UnexpectedToken, code("@1 + 2"));
+    }
+
+    /// Test for checking if single expressions are parsed
+    /// and if anything after them is rejected
+    #[test]
+    fn test_single_expression() {
+        assert!(matches!(
+            Parser::parse_single_expression("\n  price * 2 + 1\n"),
+            Ok(Expression::Infix(_))
+        ));
+        assert!(matches!(
+            Parser::parse_single_expression("[1, 2][0]"),
+            Ok(Expression::Index(_))
+        ));
+        for source in ["1 + 2 3", "var x = 1", "1\n2", ""] {
+            let err = Parser::parse_single_expression(source).unwrap_err();
+            assert_eq!(ErrorCode::UnexpectedToken, err.code(), "{}", source);
+        }
+    }
+
+    /// Test for checking if syntax errors get the code of their kind
+    #[test]
+    fn test_syntax_error_codes() {
+        let code = |source: &str| parse_source(source).unwrap_err().code();
+        assert_eq!(ErrorCode::UnexpectedToken, code("var = 1"));
+        assert_eq!(ErrorCode::MissingStatementEnd, code("var x = 1 var y = 2"));
+        assert_eq!(ErrorCode::InvalidCharacter, code("1 $ 2"));
+        assert_eq!(ErrorCode::UnterminatedString, code("\"abc"));
+        assert_eq!(ErrorCode::InvalidAssignTarget, code("1 = 2"));
+        assert_eq!(ErrorCode::InvalidAssignTarget, code("xs?[0] = 1"));
+        assert_eq!(ErrorCode::UnexpectedToken, code("if x {} else 1"));
+        assert_eq!(ErrorCode::StackedLocal, code("local local var x = 1"));
+        assert_eq!(ErrorCode::UnexpectedEof, code("{ 1"));
+        assert_eq!(ErrorCode::InvalidSyntax, code("use x"));
+    }
+
+    /// Test for checking if multiple files are joined into one program
+    /// that shares its globals and if duplicate declarations are found
+    #[test]
+    fn test_program_builder() {
+        let read = |file: &str| std::fs::read_to_string(format!("tests/parser/linking/{}", file));
+        let linked = ProgramBuilder::new()
+            .add_source("math.nx", read("math.nx").unwrap())
+            .add_source("main.nx", read("main.nx").unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(vec![1, 2, 1, 3], linked.program.lines);
+        assert_eq!(
+            vec![("math.nx".into(), 0..2), ("main.nx".into(), 2..4)],
+            linked.files
+        );
+        assert_eq!(
+            Some(Location {
+                file: "main.nx".into(),
+                line: 3,
+            }),
+            linked.location(3)
+        );
+        assert_eq!(
+            Object::Lit(Literal::Bool(true)),
+            Evaluator::new().eval_program(linked.program)
+        );
+
+        assert_eq!(
+            Err(LinkError::DuplicateDefinition {
+                name: "base".into(),
+                first: Location {
+                    file: "math.nx".into(),
+                    line: 1,
+                },
+                second: Location {
+                    file: "duplicate.nx".into(),
+                    line: 2,
+                },
+            }),
+            ProgramBuilder::new()
+                .add_source("math.nx", read("math.nx").unwrap())
+                .add_source("duplicate.nx", read("duplicate.nx").unwrap())
+                .build()
+        );
+        assert!(matches!(
+            ProgramBuilder::new()
+                .add_source("main.nx", read("main.nx").unwrap())
+                .add_source("broken.nx", "var x = 1\nvar = 2")
+                .build(),
+            Err(LinkError::Syntax { location, .. }) if location.file == "broken.nx" && location.line == 2
+        ));
+    }
+
+    fn assert_same_as_full_parse(doc: &Document) {
+        let full = parse_source(doc.source()).unwrap();
+        assert_eq!(&full, doc.program());
+        assert_eq!(all_lines(&full), all_lines(doc.program()));
+    }
+
+    /// Lines of all statements, including the ones inside of blocks
+    fn all_lines(program: &Program) -> Vec<usize> {
+        #[derive(Default)]
+        struct LineCollector(Vec<usize>);
+
+        impl Visitor for LineCollector {
+            fn visit_block(&mut self, block: &BlockStmt) {
+                self.0.extend(&block.lines);
+                crate::parser::visit::walk_block(self, block);
+            }
+        }
+
+        let mut collector = LineCollector(program.lines.clone());
+        collector.visit_program(program);
+        collector.0
     }
 }
@@ -0,0 +1,311 @@
+//! Traversal of the syntax tree.
+//!
+//! Implement [`Visitor`] (or [`VisitorMut`] to change the tree) and override
+//! the methods of the nodes you are interested in. The default methods call
+//! the matching `walk_*` function, which visits all children of the node.
+//! Overriding methods should call the `walk_*` function themselves
+//! if they want the children to be visited as well
+
+use super::ast::*;
+
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_stmt(&mut self, stmt: &Statement) {
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_expr(&mut self, expr: &Expression) {
+        walk_expr(self, expr)
+    }
+
+    fn visit_block(&mut self, block: &BlockStmt) {
+        walk_block(self, block)
+    }
+
+    /// Identifiers that are used as expressions, not names of declarations
+    fn visit_ident(&mut self, _ident: &Ident) {}
+
+    fn visit_literal(&mut self, _lit: &Literal) {}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for stmt in &program.stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Statement) {
+    match stmt {
+        Statement::Variable(var) => {
+            for arg in var
+                .annotations
+                .iter()
+                .flat_map(|annotation| &annotation.args)
+            {
+                visitor.visit_expr(arg);
+            }
+            visitor.visit_expr(&var.val);
+        }
+        Statement::Return(ret) => {
+            if let Some(val) = &ret.val {
+                visitor.visit_expr(val);
+            }
+        }
+        Statement::Local(local) => visitor.visit_stmt(&local.val),
+        Statement::Expression(expr) => visitor.visit_expr(expr),
+        Statement::Break(_) | Statement::Use(_) => (),
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &BlockStmt) {
+    for stmt in &block.stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expression) {
+    match expr {
+        Expression::Ident(ident) => visitor.visit_ident(ident),
+        Expression::Literal(lit) => visitor.visit_literal(lit),
+        Expression::Prefix(prefix) => visitor.visit_expr(&prefix.val),
+        Expression::Infix(infix) => {
+            visitor.visit_expr(&infix.left);
+            visitor.visit_expr(&infix.right);
+        }
+        Expression::Assign(assign) => {
+            visitor.visit_expr(&assign.target);
+            visitor.visit_expr(&assign.val);
+        }
+        Expression::Ternary(ternary) => {
+            visitor.visit_expr(&ternary.cond);
+            visitor.visit_expr(&ternary.then);
+            visitor.visit_expr(&ternary.alt);
+        }
+        Expression::Index(index) => {
+            visitor.visit_expr(&index.list);
+            visitor.visit_expr(&index.index);
+        }
+        Expression::Field(field) => visitor.visit_expr(&field.obj),
+        Expression::Slice(slice) => {
+            visitor.visit_expr(&slice.list);
+            for bound in [&slice.start, &slice.end].into_iter().flatten() {
+                visitor.visit_expr(bound);
+            }
+        }
+        Expression::Call(call) => {
+            visitor.visit_expr(&call.ident);
+            for arg in &call.args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expression::List(list) => {
+            for expr in &list.list {
+                visitor.visit_expr(expr);
+            }
+        }
+        Expression::Hash(hash) => {
+            for (key, val) in &hash.pairs {
+                visitor.visit_expr(key);
+                visitor.visit_expr(val);
+            }
+        }
+        Expression::If(_if) => {
+            for branch in &_if.branches {
+                visitor.visit_expr(&branch.cond);
+                visitor.visit_block(&branch.block);
+            }
+            if let Some(alt) = &_if.alt {
+                visitor.visit_block(alt);
+            }
+        }
+        Expression::Loop(_loop) => {
+            let mut cur = Some(_loop);
+            while let Some(_loop) = cur {
+                if let Some(cond) = &_loop.cond {
+                    visitor.visit_expr(cond);
+                }
+                visitor.visit_block(&_loop.block);
+                cur = _loop.alt.as_deref();
+            }
+        }
+        Expression::When(when) => {
+            if let Some(comp_val) = &when.comp_val {
+                visitor.visit_expr(comp_val);
+            }
+            for case in &when.cases {
+                for expr in [&case.comp_cond, &case.comp_val].into_iter().flatten() {
+                    visitor.visit_expr(expr);
+                }
+            }
+        }
+        Expression::Func(func) => visitor.visit_block(&func.block),
+        Expression::Block(block) => visitor.visit_block(block),
+        Expression::Annotation(annotated) => {
+            for arg in annotated
+                .annotations
+                .iter()
+                .flat_map(|annotation| &annotation.args)
+            {
+                visitor.visit_expr(arg);
+            }
+            visitor.visit_expr(&annotated.val);
+        }
+        Expression::None | Expression::Struct(_) | Expression::Enum(_) => (),
+    }
+}
+
+/// Like [`Visitor`], but allows changing the nodes while visiting them
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        walk_program_mut(self, program)
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Statement) {
+        walk_stmt_mut(self, stmt)
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expression) {
+        walk_expr_mut(self, expr)
+    }
+
+    fn visit_block_mut(&mut self, block: &mut BlockStmt) {
+        walk_block_mut(self, block)
+    }
+
+    /// Identifiers that are used as expressions, not names of declarations
+    fn visit_ident_mut(&mut self, _ident: &mut Ident) {}
+
+    fn visit_literal_mut(&mut self, _lit: &mut Literal) {}
+}
+
+pub fn walk_program_mut<V: VisitorMut + ?Sized>(visitor: &mut V, program: &mut Program) {
+    for stmt in &mut program.stmts {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Statement) {
+    match stmt {
+        Statement::Variable(var) => {
+            for arg in var
+                .annotations
+                .iter_mut()
+                .flat_map(|annotation| &mut annotation.args)
+            {
+                visitor.visit_expr_mut(arg);
+            }
+            visitor.visit_expr_mut(&mut var.val);
+        }
+        Statement::Return(ret) => {
+            if let Some(val) = &mut ret.val {
+                visitor.visit_expr_mut(val);
+            }
+        }
+        Statement::Local(local) => visitor.visit_stmt_mut(&mut local.val),
+        Statement::Expression(expr) => visitor.visit_expr_mut(expr),
+        Statement::Break(_) | Statement::Use(_) => (),
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut BlockStmt) {
+    for stmt in &mut block.stmts {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expression) {
+    match expr {
+        Expression::Ident(ident) => visitor.visit_ident_mut(ident),
+        Expression::Literal(lit) => visitor.visit_literal_mut(lit),
+        Expression::Prefix(prefix) => visitor.visit_expr_mut(&mut prefix.val),
+        Expression::Infix(infix) => {
+            visitor.visit_expr_mut(&mut infix.left);
+            visitor.visit_expr_mut(&mut infix.right);
+        }
+        Expression::Assign(assign) => {
+            visitor.visit_expr_mut(&mut assign.target);
+            visitor.visit_expr_mut(&mut assign.val);
+        }
+        Expression::Ternary(ternary) => {
+            visitor.visit_expr_mut(&mut ternary.cond);
+            visitor.visit_expr_mut(&mut ternary.then);
+            visitor.visit_expr_mut(&mut ternary.alt);
+        }
+        Expression::Index(index) => {
+            visitor.visit_expr_mut(&mut index.list);
+            visitor.visit_expr_mut(&mut index.index);
+        }
+        Expression::Field(field) => visitor.visit_expr_mut(&mut field.obj),
+        Expression::Slice(slice) => {
+            visitor.visit_expr_mut(&mut slice.list);
+            for bound in [&mut slice.start, &mut slice.end].into_iter().flatten() {
+                visitor.visit_expr_mut(bound);
+            }
+        }
+        Expression::Call(call) => {
+            visitor.visit_expr_mut(&mut call.ident);
+            for arg in &mut call.args {
+                visitor.visit_expr_mut(arg);
+            }
+        }
+        Expression::List(list) => {
+            for expr in &mut list.list {
+                visitor.visit_expr_mut(expr);
+            }
+        }
+        Expression::Hash(hash) => {
+            for (key, val) in &mut hash.pairs {
+                visitor.visit_expr_mut(key);
+                visitor.visit_expr_mut(val);
+            }
+        }
+        Expression::If(_if) => {
+            for branch in &mut _if.branches {
+                visitor.visit_expr_mut(&mut branch.cond);
+                visitor.visit_block_mut(&mut branch.block);
+            }
+            if let Some(alt) = &mut _if.alt {
+                visitor.visit_block_mut(alt);
+            }
+        }
+        Expression::Loop(_loop) => {
+            let mut cur = Some(_loop);
+            while let Some(_loop) = cur {
+                if let Some(cond) = &mut _loop.cond {
+                    visitor.visit_expr_mut(cond);
+                }
+                visitor.visit_block_mut(&mut _loop.block);
+                cur = _loop.alt.as_deref_mut();
+            }
+        }
+        Expression::When(when) => {
+            if let Some(comp_val) = &mut when.comp_val {
+                visitor.visit_expr_mut(comp_val);
+            }
+            for case in &mut when.cases {
+                for expr in [&mut case.comp_cond, &mut case.comp_val]
+                    .into_iter()
+                    .flatten()
+                {
+                    visitor.visit_expr_mut(expr);
+                }
+            }
+        }
+        Expression::Func(func) => visitor.visit_block_mut(&mut func.block),
+        Expression::Block(block) => visitor.visit_block_mut(block),
+        Expression::Annotation(annotated) => {
+            for arg in annotated
+                .annotations
+                .iter_mut()
+                .flat_map(|annotation| &mut annotation.args)
+            {
+                visitor.visit_expr_mut(arg);
+            }
+            visitor.visit_expr_mut(&mut annotated.val);
+        }
+        Expression::None | Expression::Struct(_) | Expression::Enum(_) => (),
+    }
+}
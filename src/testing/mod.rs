@@ -0,0 +1,150 @@
+//! Runner for tests written in nexus.
+//!
+//! Every function of the top level whose name starts with `test_` and
+//! that takes no arguments is a test. The program is evaluated once,
+//! then every test is called in the order it was declared. Tests fail
+//! if they return an error, e.g. from the `assert` and `assert_eq` builtins,
+//! or if the evaluator panics while running them.
+//! Each test starts with the globals of the program, so changes made by
+//! one test are not visible to the next one
+
+mod tests;
+
+use std::{
+    fmt::Display,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{
+    evaluator::{errors::EvalError, objects::Object, EvalConfig, Evaluator},
+    parser::ast::{Expression, Program, Statement},
+    util,
+};
+
+/// Functions with this prefix are tests
+pub const TEST_PREFIX: &str = "test_";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    /// Line where the test was declared
+    pub line: Option<usize>,
+    /// Why the test failed, `None` if it passed
+    pub error: Option<TestFailure>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestFailure {
+    /// The test returned an error
+    Error(EvalError),
+    /// The evaluator panicked while running the test.
+    /// Contains the message of the panic
+    Panic(String),
+}
+
+impl Display for TestFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TestFailure::Error(err) => write!(f, "{}", err),
+            TestFailure::Panic(msg) => write!(f, "panicked: {}", msg),
+        }
+    }
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TestReport {
+    pub results: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|result| result.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+/// Lists every test and its outcome, followed by the amount
+/// of passed and failed tests
+impl Display for TestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for result in &self.results {
+            match &result.error {
+                None => writeln!(f, "test {} ... ok", result.name)?,
+                Some(err) => writeln!(f, "test {} ... FAILED\n    {}", result.name, err)?,
+            }
+        }
+        write!(f, "\n{} passed, {} failed", self.passed(), self.failed())
+    }
+}
+
+/// Name and line of every test of the program, in the order they are declared
+pub fn discover(program: &Program) -> Vec<(String, Option<usize>)> {
+    program
+        .stmts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, stmt)| match stmt {
+            Statement::Variable(var)
+                if var.name.ident.0.starts_with(TEST_PREFIX)
+                    && matches!(&var.val, Expression::Func(func) if func.args.is_empty()) =>
+            {
+                Some((var.name.ident.0.clone(), program.lines.get(i).copied()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Evaluates the program and runs all of its tests.
+/// Fails if the program itself returns an error before any test ran.
+/// A panic only fails the test that caused it, the globals
+/// are restored before the next test runs
+pub fn run_tests(program: Program, config: EvalConfig) -> Result<TestReport, EvalError> {
+    let tests = discover(&program);
+    let mut evaluator = Evaluator::with_config(config);
+    if let Object::Err(err) = evaluator.eval_program(program) {
+        return Err(err);
+    }
+    let globals = evaluator.env.snapshot();
+
+    let mut report = TestReport::default();
+    for (name, line) in tests {
+        evaluator.env.restore(&globals);
+        evaluator.refuel();
+        evaluator.reset_deadline();
+        let error = match evaluator.get_function(&name) {
+            Some(func) => {
+                let ret = panic::catch_unwind(AssertUnwindSafe(|| {
+                    evaluator.call_function(&func, Vec::new())
+                }));
+                match ret {
+                    Ok(Object::Err(err)) => Some(TestFailure::Error(err)),
+                    Ok(_) => None,
+                    Err(err) => {
+                        // The scopes of the test were never popped
+                        evaluator.env.restore(&globals);
+                        Some(TestFailure::Panic(
+                            util::panic_message(&*err).unwrap_or_else(|| "Unknown error".into()),
+                        ))
+                    }
+                }
+            }
+            None => None,
+        };
+        report.results.push(TestResult { name, line, error });
+    }
+    Ok(report)
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        evaluator::{errors::EvalError, EvalConfig},
+        parser::parse_source,
+        testing::{run_tests, TestFailure, TestResult},
+    };
+
+    /// Test for checking if only `test_` functions without
+    /// arguments run and failures keep their line
+    #[test]
+    fn test_runner() {
+        let source = std::fs::read_to_string("tests/testing/tests.nx").unwrap();
+        let report = run_tests(parse_source(&source).unwrap(), EvalConfig::default()).unwrap();
+        assert_eq!(
+            vec![
+                TestResult {
+                    name: "test_double".into(),
+                    line: Some(5),
+                    error: None,
+                },
+                TestResult {
+                    name: "test_isolated".into(),
+                    line: Some(8),
+                    error: None,
+                },
+                TestResult {
+                    name: "test_fails".into(),
+                    line: Some(11),
+                    error: Some(TestFailure::Error(EvalError::AssertionFailed {
+                        msg: "4 is not equal to 5".into(),
+                        line: Some(12),
+                    })),
+                },
+            ],
+            report.results
+        );
+        assert_eq!((2, 1), (report.passed(), report.failed()));
+        assert!(!report.is_success());
+        assert!(report.to_string().ends_with("2 passed, 1 failed"));
+    }
+
+    /// Test for checking if errors of the top level are reported
+    /// instead of running the tests
+    #[test]
+    fn test_runner_setup_error() {
+        let program = parse_source("assert(1 == 2)\ntest_a :: func() {\n    1\n}").unwrap();
+        assert_eq!(
+            Err(EvalError::AssertionFailed {
+                msg: "condition is not truthy".into(),
+                line: Some(1),
+            }),
+            run_tests(program, EvalConfig::default())
+        );
+    }
+
+    /// Test for checking if a runtime error only fails the test that
+    /// caused it and if the following tests still start with the globals
+    #[test]
+    fn test_runner_isolation() {
+        let source = std::fs::read_to_string("tests/testing/failures.nx").unwrap();
+        let report = run_tests(parse_source(&source).unwrap(), EvalConfig::default()).unwrap();
+        let errors: Vec<_> = report
+            .results
+            .iter()
+            .map(|result| (result.name.as_str(), result.error.clone()))
+            .collect();
+        assert!(matches!(
+            &errors[..],
+            [
+                (
+                    "test_a",
+                    Some(TestFailure::Error(EvalError::AssertionFailed { .. }))
+                ),
+                (
+                    "test_b",
+                    Some(TestFailure::Error(EvalError::IndexOutOfBounds { .. }))
+                ),
+                ("test_c", None),
+            ]
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+use std::{cmp::Ordering, fmt::Display};
+
+/// Fixed point base-10 number used by decimal literals (`1.50d`).
+///
+/// The value is `mantissa * 10^-scale`, which means that values like
+/// `0.1` are represented exactly, unlike with `f64`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Maximum amount of digits after the decimal point.
+    /// Divisions that do not terminate get cut off here
+    pub const MAX_SCALE: u32 = 28;
+
+    pub fn new(mantissa: i128, scale: u32) -> Self {
+        Self { mantissa, scale }
+    }
+
+    /// Parses strings like `12`, `-0.5` or `1.50`.
+    /// Underscores need to be removed beforehand
+    pub fn parse(string: &str) -> Option<Self> {
+        let (negative, digits) = match string.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, string),
+        };
+        let (int, frac) = match digits.split_once('.') {
+            Some((int, frac)) => (int, frac),
+            None => (digits, ""),
+        };
+        if int.is_empty() && frac.is_empty() {
+            return None;
+        }
+        let mut mantissa: i128 = 0;
+        for ch in int.chars().chain(frac.chars()) {
+            let digit = ch.to_digit(10)? as i128;
+            mantissa = mantissa.checked_mul(10)?.checked_add(digit)?;
+        }
+        let scale = frac.len() as u32;
+        if scale > Self::MAX_SCALE {
+            return None;
+        }
+        Some(Self::new(
+            if negative { -mantissa } else { mantissa },
+            scale,
+        ))
+    }
+
+    /// Converts a float by using its shortest decimal representation,
+    /// so `0.1` becomes exactly `0.1` instead of the underlying binary value
+    pub fn from_f64(num: f64) -> Option<Self> {
+        if !num.is_finite() {
+            return None;
+        }
+        Self::parse(&num.to_string())
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (left, right, scale) = Self::align(self, other)?;
+        Some(Self::new(left.checked_add(right)?, scale))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (left, right, scale) = Self::align(self, other)?;
+        Some(Self::new(left.checked_sub(right)?, scale))
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let mut mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        let mut scale = self.scale + other.scale;
+        while scale > Self::MAX_SCALE {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        Some(Self::new(mantissa, scale))
+    }
+
+    /// Long division that stops as soon as the result is exact
+    /// or `MAX_SCALE` digits have been produced
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        let (left, right, _) = Self::align(self, other)?;
+        let negative = (left < 0) != (right < 0);
+        let (left, right) = (left.checked_abs()?, right.checked_abs()?);
+
+        let mut mantissa = left / right;
+        let mut rem = left % right;
+        let mut scale = 0;
+        while rem != 0 && scale < Self::MAX_SCALE {
+            let next = match mantissa.checked_mul(10) {
+                Some(next) => next,
+                None => break,
+            };
+            rem *= 10;
+            mantissa = next + rem / right;
+            rem %= right;
+            scale += 1;
+        }
+        Some(Self::new(if negative { -mantissa } else { mantissa }, scale).normalize())
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self::new(self.mantissa.checked_neg()?, self.scale))
+    }
+
+    /// Removes trailing zeros after the decimal point
+    pub fn normalize(mut self) -> Self {
+        while self.scale > 0 && self.mantissa % 10 == 0 {
+            self.mantissa /= 10;
+            self.scale -= 1;
+        }
+        self
+    }
+
+    /// Brings both mantissas to the same scale
+    fn align(left: Self, right: Self) -> Option<(i128, i128, u32)> {
+        let scale = left.scale.max(right.scale);
+        Some((
+            left.mantissa
+                .checked_mul(10i128.checked_pow(scale - left.scale)?)?,
+            right
+                .mantissa
+                .checked_mul(10i128.checked_pow(scale - right.scale)?)?,
+            scale,
+        ))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match Self::align(*self, *other) {
+            Some((left, right, _)) => Some(left.cmp(&right)),
+            None => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, int, frac)
+    }
+}
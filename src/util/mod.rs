@@ -1,26 +1,29 @@
-use crate::{parser::ast::{OptionallyTypedIdent, BlockStmt}, lexer::{Lexer, tokens::Token}};
+pub mod decimal;
 
-pub fn get_next_tok(lexer: &mut Lexer) -> Token {
+use std::any::Any;
+
+use crate::{
+    lexer::{tokens::Token, Lexer},
+    parser::{
+        ast::{BlockStmt, OptionallyTypedIdent},
+        SyntaxError,
+    },
+};
+
+pub fn get_next_tok(lexer: &mut Lexer) -> Result<Token, SyntaxError> {
     loop {
-        let tok = lexer.tokenize();
+        let tok = lexer.tokenize()?;
         if let Some(tok) = tok {
-            return tok;
+            return Ok(tok);
         }
     }
 }
 
 pub fn typed_vec_to_string(val: &[OptionallyTypedIdent]) -> String {
-    let mut buf = String::new();
-    val.iter().for_each(|i| {
-        let str = format!("{}{}", &i.ident.0, match &i._type {
-            Some(_type) => format!(": {}", &_type.0),
-            None => String::new(),
-        });
-        buf.push_str(&str);
-        buf.push(',')
-    });
-    buf.pop();
-    buf
+    val.iter()
+        .map(|ident| ident.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
 pub fn block_to_string(val: &BlockStmt) -> String {
@@ -31,3 +34,51 @@ pub fn block_to_string(val: &BlockStmt) -> String {
     });
     buf
 }
+
+/// Amount of single character insertions, deletions, substitutions
+/// and swaps of neighbouring characters that turn one string into the other
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let left: Vec<char> = left.chars().collect();
+    let right: Vec<char> = right.chars().collect();
+    // Distances between the prefixes of left and right, one row per char of left
+    let mut rows = vec![(0..=right.len()).collect::<Vec<usize>>()];
+    for i in 1..=left.len() {
+        let mut row = vec![i; right.len() + 1];
+        for j in 1..=right.len() {
+            let cost = usize::from(left[i - 1] != right[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[left.len()][right.len()]
+}
+
+/// Candidate that is closest to the name, if it is close enough to be a typo.
+/// A third of the characters of the name may be wrong, but at least one.
+/// Ties are broken alphabetically, so the result does not depend on the order
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Message of a caught panic, if it panicked with a string
+pub fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|msg| msg.to_string()))
+}
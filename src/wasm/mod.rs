@@ -0,0 +1,94 @@
+//! Bindings for JavaScript, behind the `wasm` feature.
+//!
+//! Build with `cargo build --lib --target wasm32-unknown-unknown --features wasm`
+//! and generate the glue code with `wasm-bindgen`.
+//!
+//! There are no threads, clocks, stdin or stdout on wasm32-unknown-unknown:
+//! `print` is collected into [`EvalOutput::output`], `input` always reads an
+//! empty line, timeouts are ignored, `spawn` fails and `sleep` returns
+//! right away.
+//!
+//! Syntax and runtime errors are returned like on any other target.
+//! Panics abort instead of unwinding on wasm32-unknown-unknown, so a bug
+//! in the interpreter traps with a `RuntimeError` in JavaScript and kills
+//! the instance. Create a new instance after a trap
+
+mod tests;
+
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    evaluator::{io::BufferedIo, objects::Object, EvalConfig, Evaluator},
+    lsp,
+    parser::parse_source,
+};
+
+/// Timeouts do not work without a clock, so fuel is the
+/// only thing that stops an endless loop from freezing the page
+const FUEL: u64 = 10_000_000;
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalOutput {
+    /// Everything that was printed
+    pub output: String,
+    /// Value of the last statement, `undefined` if the program failed
+    pub result: Option<String>,
+    /// Syntax or runtime error, `undefined` if the program succeeded
+    pub error: Option<String>,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Starting at 1
+    pub line: usize,
+    /// Code of the error like `E0101`
+    pub code: String,
+    pub message: String,
+}
+
+/// Parses and runs the source code
+#[wasm_bindgen]
+pub fn eval_source(source: &str) -> EvalOutput {
+    let program = match parse_source(source) {
+        Ok(program) => program,
+        Err(err) => {
+            return EvalOutput {
+                output: String::new(),
+                result: None,
+                error: Some(err.msg),
+            }
+        }
+    };
+    let io = Arc::new(BufferedIo::default());
+    let mut evaluator = Evaluator::with_config(EvalConfig {
+        fuel: Some(FUEL),
+        ..Default::default()
+    });
+    evaluator.set_io(io.clone());
+    let (result, error) = match evaluator.eval_program(program) {
+        Object::Err(err) => (None, Some(err.to_string())),
+        obj => (Some(obj.to_string()), None),
+    };
+    EvalOutput {
+        output: io.take_output(),
+        result,
+        error,
+    }
+}
+
+/// Syntax errors of the source code, see `lsp::diagnostics`
+#[wasm_bindgen]
+pub fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    lsp::diagnostics(source)
+        .into_iter()
+        .map(|diagnostic| Diagnostic {
+            line: diagnostic.line,
+            code: diagnostic.code.code().into(),
+            message: diagnostic.message,
+        })
+        .collect()
+}
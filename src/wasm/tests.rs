@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod tests {
+    use crate::wasm::{diagnostics, eval_source, Diagnostic, EvalOutput};
+
+    /// Test for checking if syntax errors are returned
+    /// instead of trapping the instance
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(
+            vec![Diagnostic {
+                line: 1,
+                code: "E0101".into(),
+                message: "Expected an identifier, received: =".into(),
+            }],
+            diagnostics("var = 1")
+        );
+        assert!(diagnostics("var x = 1").is_empty());
+        assert_eq!(
+            EvalOutput {
+                output: String::new(),
+                result: None,
+                error: Some("Expected an identifier, received: =".into()),
+            },
+            eval_source("var = 1")
+        );
+    }
+
+    /// Test for checking if printed output and the
+    /// value of the last statement are returned
+    #[test]
+    fn test_eval_source() {
+        assert_eq!(
+            EvalOutput {
+                output: "3\n".into(),
+                result: Some("4".into()),
+                error: None,
+            },
+            eval_source("print(1 + 2)\n4")
+        );
+    }
+}